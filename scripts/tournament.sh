@@ -17,6 +17,9 @@ ENGINE2="./versions/${ENGINE2_NAME}"
 TC=${3:-"40/60+10"}
 # Default rounds: 2
 ROUNDS=${4:-"20"}
+# Extra UCI options per engine, e.g. ENGINE2_OPTIONS="option.LateMoveReductions=false"
+ENGINE1_OPTIONS=${ENGINE1_OPTIONS:-""}
+ENGINE2_OPTIONS=${ENGINE2_OPTIONS:-""}
 
 PGN_DIR="games"
 PGN_FILE="${PGN_DIR}/${ENGINE1_NAME}-vs-${ENGINE2_NAME}-R${ROUNDS}.pgn"
@@ -31,8 +34,8 @@ echo "Time Control: ${TC}"
 echo "Rounds: ${ROUNDS}"
 echo "PGN Output: ${PGN_FILE}"
 
-cutechess-cli -engine cmd="${ENGINE1}" name="${ENGINE1_NAME}" \
--engine cmd="${ENGINE2}" name="${ENGINE2_NAME}" \
+cutechess-cli -engine cmd="${ENGINE1}" name="${ENGINE1_NAME}" ${ENGINE1_OPTIONS} \
+-engine cmd="${ENGINE2}" name="${ENGINE2_NAME}" ${ENGINE2_OPTIONS} \
 -each proto=uci tc="${TC}" -rounds "${ROUNDS}" -pgnout "${PGN_FILE}" \
 -openings file=openings/silver-suite.txt format=pgn
 
@@ -1,15 +1,16 @@
 use csv::{Writer, WriterBuilder};
 use hhz::board::Board;
 use hhz::metrics::{SearchMetrics, SearchMetricsData};
-use hhz::search::search_entry;
+use hhz::search::{SearchConfig, SearchContext, SearchResult, search_entry};
 use hhz::tt_table::TT_Table;
 use std::fs::OpenOptions;
 use std::time::{Instant, SystemTime};
 
-pub mod generate_attack_lookup;
-
 static FEATURE_NAME: &str = "higher-depths";
 static FEATURE_NUMBER: u32 = 58;
+// switch techniques off here to compare against them, e.g.
+// SearchConfig { late_move_reductions: false, ..SearchConfig::DEFAULT }
+const SEARCH_CONFIG: SearchConfig = SearchConfig::DEFAULT;
 
 fn main() {
     println!("board size: {}", std::mem::size_of::<Board>());
@@ -66,7 +67,10 @@ fn main() {
     for (position_name, fen) in positions {
         println!("\nPosition: {}", fen);
 
-        let mut tt_table = TT_Table::new();
+        let tt_table = TT_Table::new();
+        let mut search_context = SearchContext::new();
+        search_context.config = SEARCH_CONFIG;
+        let mut previous_result: Option<SearchResult> = None;
         for depth in 0..max_depth + 1 {
             println!(
                 "\nSearching at depth {}, current time: {}, for {}",
@@ -80,10 +84,20 @@ fn main() {
 
             SearchMetrics::new_measurement(FEATURE_NAME, depth, position_name, fen);
 
-            let board = Board::from_fen(fen).unwrap();
+            let mut board = Board::from_fen(fen).unwrap();
             let start = Instant::now();
 
-            let best_move = search_entry(&board, depth, &mut tt_table, & mut [0;100],0);
+            let result = search_entry(
+                &mut board,
+                depth,
+                previous_result.as_ref().map(|result| result.score),
+                &tt_table,
+                &mut search_context,
+                &mut [0; 100],
+                0,
+            );
+            let best_move = result.as_ref().map(|result| result.best_move);
+            previous_result = result;
 
             let elapsed = start.elapsed();
 
@@ -200,9 +214,25 @@ fn calculate_and_write_summary(all_metrics: &[SearchMetricsData], target_depth:
 }
 
 fn write_summary_record(data: &SearchMetricsData) -> Result<(), Box<dyn std::error::Error>> {
-    let summary_path = "benchmarks/summary_by_version.csv";
+    // v1 has no columns for the killer, history and search technique counters.
+    let summary_path = "benchmarks/summary_by_version_v2.csv";
     let file_exists = std::path::Path::new(summary_path).exists();
 
+    // Appending rows with other columns than the header would misalign them.
+    if file_exists {
+        let mut header_wtr = WriterBuilder::new().from_writer(vec![]);
+        header_wtr.serialize(data)?;
+        let csv = String::from_utf8(header_wtr.into_inner()?)?;
+        let existing = std::fs::read_to_string(summary_path)?;
+        if existing.lines().next() != csv.lines().next() {
+            return Err(format!(
+                "The columns of {} don't match the metrics, start a new summary file",
+                summary_path
+            )
+            .into());
+        }
+    }
+
     let file = OpenOptions::new()
         .write(true)
         .create(true)
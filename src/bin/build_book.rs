@@ -0,0 +1,132 @@
+use hhz::book::{BookEntry, encode_move};
+use hhz::pgn::PgnGame;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+
+const DEFAULT_MAX_PLY: usize = 24;
+const DEFAULT_MIN_COUNT: u32 = 1;
+
+/// Builds a Polyglot book out of PGN files.
+///
+/// Usage: build_book <output.bin> [--max-ply N] [--min-count N] <input.pgn>...
+///
+/// Every move gets 2 points for a win, 1 for a draw and 0 for a loss of the side that played it.
+/// Moves that were played fewer than `--min-count` times or never scored are left out.
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let mut output_path = None;
+    let mut input_paths = Vec::new();
+    let mut max_ply = DEFAULT_MAX_PLY;
+    let mut min_count = DEFAULT_MIN_COUNT;
+
+    let mut args_iter = args.iter();
+    while let Some(arg) = args_iter.next() {
+        match arg.as_str() {
+            "--max-ply" => {
+                max_ply = args_iter
+                    .next()
+                    .and_then(|v| v.parse().ok())
+                    .expect("--max-ply needs a number");
+            }
+            "--min-count" => {
+                min_count = args_iter
+                    .next()
+                    .and_then(|v| v.parse().ok())
+                    .expect("--min-count needs a number");
+            }
+            _ if output_path.is_none() => output_path = Some(arg.clone()),
+            _ => input_paths.push(arg.clone()),
+        }
+    }
+
+    let Some(output_path) = output_path else {
+        eprintln!("Usage: build_book <output.bin> [--max-ply N] [--min-count N] <input.pgn>...");
+        std::process::exit(1);
+    };
+    if input_paths.is_empty() {
+        eprintln!("No PGN files given");
+        std::process::exit(1);
+    }
+
+    // (zobrist, polyglot move) -> (times played, score)
+    let mut stats: HashMap<(u64, u16), (u32, u32)> = HashMap::new();
+    let mut num_games = 0;
+
+    for path in &input_paths {
+        let content =
+            fs::read_to_string(path).unwrap_or_else(|e| panic!("Could not read {}: {}", path, e));
+        for (index, game) in PgnGame::parse_all(&content).into_iter().enumerate() {
+            match game {
+                Ok(game) => {
+                    num_games += 1;
+                    add_game(&game, max_ply, &mut stats);
+                }
+                Err(e) => eprintln!("Skipping game {} of {}: {}", index + 1, path, e),
+            }
+        }
+    }
+
+    let mut entries: Vec<BookEntry> = Vec::new();
+    let mut by_position: HashMap<u64, Vec<(u16, u32)>> = HashMap::new();
+    for ((key, raw_move), (count, score)) in stats {
+        if count >= min_count && score > 0 {
+            by_position.entry(key).or_default().push((raw_move, score));
+        }
+    }
+    for (key, moves) in by_position {
+        // Polyglot weights are 16 bit, so scale positions with a lot of games down
+        let max_score = moves.iter().map(|(_, s)| *s).max().unwrap_or(0);
+        let divisor = max_score.div_ceil(u16::MAX as u32).max(1);
+        for (raw_move, score) in moves {
+            entries.push(BookEntry {
+                key,
+                raw_move,
+                weight: (score / divisor).max(1) as u16,
+                learn: 0,
+            });
+        }
+    }
+
+    // Polyglot readers binary search by key, best moves first is the usual convention
+    entries.sort_by(|a, b| a.key.cmp(&b.key).then(b.weight.cmp(&a.weight)));
+
+    let mut file = fs::File::create(&output_path)
+        .unwrap_or_else(|e| panic!("Could not create {}: {}", output_path, e));
+    for entry in &entries {
+        file.write_all(&entry.to_bytes()).unwrap();
+    }
+
+    println!(
+        "Wrote {} entries from {} games to {}",
+        entries.len(),
+        num_games,
+        output_path
+    );
+}
+
+fn add_game(game: &PgnGame, max_ply: usize, stats: &mut HashMap<(u64, u16), (u32, u32)>) {
+    // score for white, black gets the mirrored score
+    let white_score = match game.result() {
+        "1-0" => 2,
+        "0-1" => 0,
+        // unfinished games (like opening suites) count as draws
+        _ => 1,
+    };
+
+    for index in game.mainline_nodes().into_iter().take(max_ply) {
+        let node = game.node(index);
+        let board = game.board_at(node.parent);
+        let score = if board.white_to_move {
+            white_score
+        } else {
+            2 - white_score
+        };
+        let entry = stats
+            .entry((board.zobrist_hash, encode_move(&node._move)))
+            .or_insert((0, 0));
+        entry.0 += 1;
+        entry.1 += score;
+    }
+}
@@ -0,0 +1,285 @@
+use csv::{Writer, WriterBuilder};
+use hhz::bot::{Bot, BotMessage, SearchSpecs};
+use hhz::epd::EpdPosition;
+use hhz::moves::Move;
+use hhz::search::{SearchContext, SearchResult, search_entry};
+use hhz::tt_table::TT_Table;
+use serde::Serialize;
+use std::fs::{self, OpenOptions};
+use std::path::Path;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+static FEATURE_NAME: &str = "epd-runner";
+const DEFAULT_DEPTH: u8 = 6;
+
+#[derive(Debug, Clone, Copy)]
+enum Limit {
+    Depth(u8),
+    MoveTime(Duration),
+}
+
+#[derive(Debug, Serialize)]
+struct EpdResult {
+    feature_name: String,
+    suite: String,
+    id: String,
+    fen: String,
+    expected: String,
+    found: String,
+    solved: bool,
+    depth: u8,
+    time_ms: u128,
+    comment: String,
+}
+
+#[derive(Debug, Serialize)]
+struct EpdSummary {
+    feature_name: String,
+    suite: String,
+    limit: String,
+    solved: usize,
+    total: usize,
+    solved_pct: f64,
+    total_time_ms: u128,
+}
+
+/// Runs an EPD test suite and reports which positions were solved.
+///
+/// Usage: epd <suite.epd> [--depth N | --movetime MS] [--name NAME]
+///
+/// With `--depth` every position is searched with `search_entry` up to that depth,
+/// with `--movetime` the `Bot` gets that much time per position.
+/// Results go to `benchmarks/epd/<suite>_<name>.csv`, the totals are appended to
+/// `benchmarks/epd/summary.csv`.
+fn main() {
+    if cfg!(debug_assertions) {
+        println!("Warning: not in release mode, search times are not comparable");
+    }
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut suite_path = None;
+    let mut limit = Limit::Depth(DEFAULT_DEPTH);
+    let mut feature_name = FEATURE_NAME.to_string();
+
+    let mut args_iter = args.iter();
+    while let Some(arg) = args_iter.next() {
+        match arg.as_str() {
+            "--depth" => {
+                limit = Limit::Depth(
+                    args_iter
+                        .next()
+                        .and_then(|v| v.parse().ok())
+                        .expect("--depth needs a number"),
+                );
+            }
+            "--movetime" => {
+                limit = Limit::MoveTime(Duration::from_millis(
+                    args_iter
+                        .next()
+                        .and_then(|v| v.parse().ok())
+                        .expect("--movetime needs a number of milliseconds"),
+                ));
+            }
+            "--name" => {
+                feature_name = args_iter.next().expect("--name needs a value").clone();
+            }
+            _ => suite_path = Some(arg.clone()),
+        }
+    }
+
+    let Some(suite_path) = suite_path else {
+        eprintln!("Usage: epd <suite.epd> [--depth N | --movetime MS] [--name NAME]");
+        std::process::exit(1);
+    };
+
+    let content = fs::read_to_string(&suite_path)
+        .unwrap_or_else(|e| panic!("Could not read {}: {}", suite_path, e));
+    let positions = EpdPosition::parse_all(&content)
+        .unwrap_or_else(|e| panic!("Could not parse {}: {}", suite_path, e));
+    let suite = Path::new(&suite_path)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| suite_path.clone());
+
+    let results_dir = "benchmarks/epd";
+    fs::create_dir_all(results_dir).expect("Failed to create benchmarks/epd");
+    let file_path = format!("{}/{}_{}.csv", results_dir, suite, feature_name);
+    if Path::new(&file_path).exists() {
+        panic!(
+            "Results file {} already exists. Please remove it or pass another --name.",
+            file_path
+        );
+    }
+    let mut writer = Writer::from_path(&file_path)
+        .unwrap_or_else(|e| panic!("Failed to create CSV writer for {}: {}", file_path, e));
+
+    println!(
+        "EPD suite {} ({} positions), {:?}",
+        suite,
+        positions.len(),
+        limit
+    );
+    println!(
+        "{:>4}  {:<40} {:<10} {:<10} {:>5} {:>8}  result",
+        "#", "id", "expected", "found", "depth", "ms"
+    );
+
+    let mut solved = 0;
+    let mut total_time_ms = 0;
+    for (index, position) in positions.iter().enumerate() {
+        let start = Instant::now();
+        let (found, depth) = match limit {
+            Limit::Depth(max_depth) => search_to_depth(position, max_depth),
+            Limit::MoveTime(move_time) => search_with_bot(position, move_time),
+        };
+        let time_ms = start.elapsed().as_millis();
+        total_time_ms += time_ms;
+
+        let is_solved = found.is_some_and(|m| position.is_solved_by(&m));
+        if is_solved {
+            solved += 1;
+        }
+
+        let expected = expected_moves(position);
+        let found_san = found
+            .map(|m| position.board.move_to_san(&m))
+            .unwrap_or_else(|| "-".to_string());
+        println!(
+            "{:>4}  {:<40} {:<10} {:<10} {:>5} {:>8}  {}",
+            index + 1,
+            position.id().unwrap_or("?"),
+            expected,
+            found_san,
+            depth,
+            time_ms,
+            if is_solved { "pass" } else { "FAIL" }
+        );
+
+        writer
+            .serialize(EpdResult {
+                feature_name: feature_name.clone(),
+                suite: suite.clone(),
+                id: position.id().unwrap_or("").to_string(),
+                fen: position.board.to_fen(),
+                expected,
+                found: found_san,
+                solved: is_solved,
+                depth,
+                time_ms,
+                comment: position.comment().unwrap_or("").to_string(),
+            })
+            .unwrap();
+        writer.flush().unwrap();
+    }
+
+    let total = positions.len();
+    let solved_pct = if total == 0 {
+        0.0
+    } else {
+        solved as f64 * 100.0 / total as f64
+    };
+    println!(
+        "\nSolved {}/{} ({:.1}%) in {:.1}s",
+        solved,
+        total,
+        solved_pct,
+        total_time_ms as f64 / 1000.0
+    );
+    println!("Results written to {}", file_path);
+
+    let limit = match limit {
+        Limit::Depth(depth) => format!("depth {}", depth),
+        Limit::MoveTime(move_time) => format!("movetime {}ms", move_time.as_millis()),
+    };
+    write_summary_record(&EpdSummary {
+        feature_name,
+        suite,
+        limit,
+        solved,
+        total,
+        solved_pct,
+        total_time_ms,
+    })
+    .expect("Failed to write summary record");
+}
+
+fn expected_moves(position: &EpdPosition) -> String {
+    let best_moves: Vec<String> = position
+        .best_moves
+        .iter()
+        .map(|m| position.board.move_to_san(m))
+        .collect();
+    let avoid_moves: Vec<String> = position
+        .avoid_moves
+        .iter()
+        .map(|m| format!("!{}", position.board.move_to_san(m)))
+        .collect();
+    [best_moves, avoid_moves].concat().join(" ")
+}
+
+/// Iterative deepening up to `max_depth` with a fresh transposition table.
+fn search_to_depth(position: &EpdPosition, max_depth: u8) -> (Option<Move>, u8) {
+    let mut board = position.board;
+    let tt_table = TT_Table::new();
+    let mut search_context = SearchContext::new();
+    let mut best_result: Option<SearchResult> = None;
+    let mut reached_depth = 0;
+    for depth in 0..=max_depth {
+        let result = search_entry(
+            &mut board,
+            depth,
+            best_result.as_ref().map(|result| result.score),
+            &tt_table,
+            &mut search_context,
+            &mut [0; 100],
+            0,
+        );
+        if result.is_none() {
+            break;
+        }
+        best_result = result;
+        reached_depth = depth;
+    }
+    (best_result.map(|result| result.best_move), reached_depth)
+}
+
+fn search_with_bot(position: &EpdPosition, move_time: Duration) -> (Option<Move>, u8) {
+    let (result_tx, result_rx) = mpsc::channel();
+    let mut bot = Bot::new(result_tx);
+    bot.set_position(position.board, [0; 100], 0);
+    bot.start_searching(SearchSpecs::MoveTime(move_time));
+
+    let mut reached_depth = 0;
+    let mut best_move = None;
+    for message in &result_rx {
+        match message {
+            BotMessage::Info(info) => reached_depth = info.depth,
+            BotMessage::BestMove(m) => {
+                best_move = Some(m);
+                break;
+            }
+        }
+    }
+    bot.quit();
+    (best_move, reached_depth)
+}
+
+fn write_summary_record(data: &EpdSummary) -> Result<(), Box<dyn std::error::Error>> {
+    let summary_path = "benchmarks/epd/summary.csv";
+    let file_exists = Path::new(summary_path).exists();
+
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(summary_path)?;
+
+    let mut wtr = WriterBuilder::new()
+        .has_headers(!file_exists)
+        .from_writer(file);
+
+    wtr.serialize(data)?;
+    wtr.flush()?;
+    println!("Appended summary to {}", summary_path);
+    Ok(())
+}
@@ -1,7 +1,10 @@
 use chrono::Local;
 use chrono::DateTime;
 use hhz::board::Board;
-use hhz::search::search_entry;
+use hhz::eval::{EvalBreakdown, TermScores, eval_trace};
+use hhz::moves::Move;
+use hhz::search::{SearchContext, search_entry};
+use hhz::tt_table::{DEFAULT_SIZE_MB, TT_Table};
 use rouille::Request;
 use rouille::input::json_input;
 use rouille::{Response, router, try_or_400};
@@ -14,6 +17,26 @@ struct GameRequest {
     pub color: String,
 }
 
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct EvalRequest {
+    pub fen: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct EvalTermResponse {
+    pub term: String,
+    pub white: (i32, i32),
+    pub black: (i32, i32),
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct EvalResponse {
+    pub phase: u8,
+    pub terms: Vec<EvalTermResponse>,
+    pub total: i16,
+    pub table: String,
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 struct MoveRepresentation {
     pub uci_move: String,
@@ -48,6 +71,7 @@ fn main() {
         router!(request,
             (POST) (/startgame) => {new_game(request, board.clone(), depth)},
             (POST) (/move) => { on_move(request, board.clone(), depth)},
+            (POST) (/eval) => { on_eval(request) },
             _ => Response::text("Not found").with_status_code(404)
         )
         .with_additional_header("Access-Control-Allow-Origin", "*")
@@ -64,18 +88,15 @@ fn new_game(request: &Request, board: Arc<Mutex<Board>>, depth: u8) -> Response
             *board_guard = new_board;
             println!("game fen: {}", game_request.fen);
             if new_board.white_to_move == (game_request.color == "white") {
-                let next_move = search_entry(&new_board, depth);
+                let next_move = find_move(new_board, depth)
+                    .expect("No move found, maybe stalemate or checkmate?");
                 // Make the AI's move
-                let after_my_move = new_board.make_move_temp(
-                    next_move.expect("No move found, maybe stalemate or checkmate?"),
-                );
+                let after_my_move = new_board.make_move_temp(&next_move);
 
                 *board_guard = after_my_move;
 
                 Response::json(&MoveRepresentation {
-                    uci_move: next_move
-                        .expect("No move found, maybe stalemate or checkmate?")
-                        .to_uci(),
+                    uci_move: next_move.to_uci(),
                     resul_fen: after_my_move.to_fen(),
                 })
                 .with_status_code(200)
@@ -106,7 +127,7 @@ fn on_move(request: &Request, board: Arc<Mutex<Board>>, depth: u8) -> Response {
         }
     };
     // Make the move
-    let new_board = old_board.make_move_temp(requested_move);
+    let new_board = old_board.make_move_temp(&requested_move);
     drop(old_board);
     // Check if the FEN matches what was expected
     let current_fen = new_board.to_fen();
@@ -121,18 +142,108 @@ fn on_move(request: &Request, board: Arc<Mutex<Board>>, depth: u8) -> Response {
         ))
         .with_status_code(409); // 409 Conflict
     }
-    let next_move = search_entry(&new_board, depth);
+    let next_move =
+        find_move(new_board, depth).expect("No move found, maybe stalemate or checkmate?");
     // Make the AI's move
-    let after_my_move =
-        new_board.make_move_temp(next_move.expect("No move found, maybe stalemate or checkmate?"));
+    let after_my_move = new_board.make_move_temp(&next_move);
 
     *board.lock().unwrap() = after_my_move;
 
     return Response::json(&MoveRepresentation {
-        uci_move: next_move
-            .expect("No move found, maybe stalemate or checkmate?")
-            .to_uci(),
+        uci_move: next_move.to_uci(),
         resul_fen: after_my_move.to_fen(),
     })
     .with_status_code(200);
 }
+
+/// Searches `board` to a fixed depth. The server keeps no game history, so repetitions are
+/// not seen.
+fn find_move(mut board: Board, depth: u8) -> Option<Move> {
+    let tt_table = TT_Table::with_size_mb(DEFAULT_SIZE_MB);
+    let mut search_context = SearchContext::new();
+    search_entry(
+        &mut board,
+        depth,
+        None,
+        &tt_table,
+        &mut search_context,
+        &mut [0; 100],
+        0,
+    )
+    .map(|result| result.best_move)
+}
+
+fn on_eval(request: &Request) -> Response {
+    let eval_request: EvalRequest = try_or_400!(json_input(request));
+    let board = match Board::from_fen(&eval_request.fen) {
+        Ok(board) => board,
+        Err(e) => {
+            eprintln!("Invalid FEN: {}", e);
+            return Response::text(format!("Invalid FEN: {}", e)).with_status_code(400);
+        }
+    };
+    let breakdown: EvalBreakdown = eval_trace(&board);
+    let term = |name: &str, scores: TermScores| EvalTermResponse {
+        term: name.to_string(),
+        white: scores.white,
+        black: scores.black,
+    };
+    Response::json(&EvalResponse {
+        phase: breakdown.phase,
+        terms: vec![
+            term("material", breakdown.material),
+            term("piece_squares", breakdown.piece_squares),
+            term("pawn_structure", breakdown.pawn_structure),
+            term("pieces", breakdown.pieces),
+            term("mobility", breakdown.mobility),
+            term("king_safety", breakdown.king_safety),
+        ],
+        total: breakdown.total(),
+        table: breakdown.to_string(),
+    })
+    .with_status_code(200)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hhz::eval::{EvalParams, eval};
+    use hhz::pawn_structure::PawnTable;
+    use std::io::Read;
+
+    #[test]
+    fn test_eval_total_matches_eval() {
+        let fen = "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4";
+        let request = Request::fake_http(
+            "POST",
+            "/eval",
+            vec![("Content-Type".to_owned(), "application/json".to_owned())],
+            format!(r#"{{"fen": "{}"}}"#, fen).into_bytes(),
+        );
+        let response = on_eval(&request);
+        assert_eq!(response.status_code, 200);
+
+        let mut body = String::new();
+        response
+            .data
+            .into_reader_and_size()
+            .0
+            .read_to_string(&mut body)
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+        let board = Board::from_fen(fen).unwrap();
+        let total = eval(&board, &EvalParams::DEFAULT, &mut PawnTable::new());
+        assert_eq!(json["total"], total);
+
+        let material = json["terms"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|term| term["term"] == "material")
+            .unwrap();
+        for side in ["white", "black"] {
+            let (middlegame, endgame) = (&material[side][0], &material[side][1]);
+            assert!(middlegame.as_i64().unwrap() != 0 && endgame.as_i64().unwrap() != 0);
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use hhz::board::{Board, DEFAULT_FEN};
+use hhz::perft::{PERFT_SUITE, PerftTable, divide, perft, perft_bulk, perft_hashed};
+use std::time::Instant;
+
+/// Counts the leaf nodes of the move tree to validate the move generator.
+///
+/// Usage:
+///   perft [options] <depth> [fen]         count nodes
+///   perft [options] divide <depth> [fen]  count nodes per root move
+///   perft [options] suite [max_depth]     check the standard positions against their known counts
+///
+/// Options:
+///   --bulk       count the last ply by the number of legal moves instead of making them
+///   --hash <MB>  bulk counting with a hash table for transpositions
+fn main() {
+    let mut counter = Counter::MakeMoves;
+    let mut args = Vec::new();
+
+    let mut args_iter = std::env::args().skip(1);
+    while let Some(arg) = args_iter.next() {
+        match arg.as_str() {
+            "--bulk" => {
+                if let Counter::MakeMoves = counter {
+                    counter = Counter::Bulk;
+                }
+            }
+            "--hash" => {
+                let size_mb = args_iter
+                    .next()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or_else(|| usage());
+                counter = Counter::Hashed(PerftTable::with_size_mb(size_mb));
+            }
+            _ => args.push(arg),
+        }
+    }
+
+    match args.first().map(String::as_str) {
+        Some("suite") => {
+            let max_depth = args.get(1).map(|d| parse_depth(d)).unwrap_or(5);
+            run_suite(&mut counter, max_depth);
+        }
+        Some("divide") => {
+            let depth = args
+                .get(1)
+                .map(|d| parse_depth(d))
+                .unwrap_or_else(|| usage());
+            run_divide(&mut counter, &mut parse_board(&args[2..]), depth);
+        }
+        Some(depth) => run_perft(
+            &mut counter,
+            &mut parse_board(&args[1..]),
+            parse_depth(depth),
+        ),
+        None => usage(),
+    }
+}
+
+enum Counter {
+    MakeMoves,
+    Bulk,
+    Hashed(PerftTable),
+}
+
+impl Counter {
+    fn count(&mut self, board: &mut Board, depth: u8) -> u64 {
+        match self {
+            Counter::MakeMoves => perft(board, depth),
+            Counter::Bulk => perft_bulk(board, depth),
+            Counter::Hashed(table) => perft_hashed(board, depth, table),
+        }
+    }
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "Usage: perft [--bulk] [--hash MB] <depth> [fen] | divide <depth> [fen] | suite [max_depth]"
+    );
+    std::process::exit(1);
+}
+
+fn parse_depth(depth: &str) -> u8 {
+    depth.parse().unwrap_or_else(|_| usage())
+}
+
+fn parse_board(fen_parts: &[String]) -> Board {
+    let fen = if fen_parts.is_empty() {
+        DEFAULT_FEN.to_string()
+    } else {
+        fen_parts.join(" ")
+    };
+    Board::from_fen(&fen).unwrap_or_else(|e| {
+        eprintln!("Invalid FEN {}: {}", fen, e);
+        std::process::exit(1);
+    })
+}
+
+fn print_speed(nodes: u64, start: Instant) {
+    let elapsed = start.elapsed();
+    println!(
+        "Time: {:.3} s, {:.0} nodes/s",
+        elapsed.as_secs_f64(),
+        nodes as f64 / elapsed.as_secs_f64().max(f64::EPSILON)
+    );
+}
+
+fn run_perft(counter: &mut Counter, board: &mut Board, depth: u8) {
+    let start = Instant::now();
+    let nodes = counter.count(board, depth);
+    println!("Nodes: {}", nodes);
+    print_speed(nodes, start);
+}
+
+fn run_divide(counter: &mut Counter, board: &mut Board, depth: u8) {
+    let start = Instant::now();
+    let counts = divide(board, depth, |board, depth| counter.count(board, depth));
+    for (_move, nodes) in &counts {
+        println!("{}: {}", _move.to_uci(), nodes);
+    }
+    let nodes = counts.iter().map(|(_, n)| n).sum();
+    println!("\nMoves: {}", counts.len());
+    println!("Nodes: {}", nodes);
+    print_speed(nodes, start);
+}
+
+fn run_suite(counter: &mut Counter, max_depth: u8) {
+    let mut failures = 0;
+    let mut total_nodes = 0;
+    let start = Instant::now();
+
+    for position in &PERFT_SUITE {
+        let mut board = Board::from_fen(position.fen).unwrap();
+        println!("{}: {}", position.name, position.fen);
+        for (depth, expected) in position.nodes.iter().enumerate().take(max_depth as usize) {
+            let depth = depth as u8 + 1;
+            let depth_start = Instant::now();
+            let nodes = counter.count(&mut board, depth);
+            total_nodes += nodes;
+            let result = if nodes == *expected {
+                "ok"
+            } else {
+                failures += 1;
+                "FAIL"
+            };
+            println!(
+                "  depth {}: {:>12} expected {:>12} {:>8.3} s  {}",
+                depth,
+                nodes,
+                expected,
+                depth_start.elapsed().as_secs_f64(),
+                result
+            );
+        }
+    }
+
+    println!();
+    print_speed(total_nodes, start);
+    if failures > 0 {
+        println!("{} counts did not match", failures);
+        std::process::exit(1);
+    }
+    println!("All counts match");
+}
@@ -1,6 +1,12 @@
 use chrono::Duration;
 use hhz::board::{Board, DEFAULT_FEN};
-use hhz::bot::{Bot, BotMessage, SearchSpecs};
+use hhz::book::{BookSelection, PolyglotBook};
+use hhz::bot::{Bot, BotMessage, Score, SearchSpecs};
+use hhz::eval::eval_trace;
+use hhz::moves::Move;
+use hhz::search::SearchConfig;
+use hhz::time_manager::DEFAULT_MOVE_OVERHEAD;
+use hhz::tt_table::DEFAULT_SIZE_MB;
 use log::{LevelFilter, error, info};
 use std::io::{self, BufRead, Write};
 use std::panic;
@@ -10,7 +16,9 @@ use std::sync::mpsc;
 use std::thread;
 use std::{env, fs};
 use vampirc_uci::UciTimeControl;
-use vampirc_uci::{UciInfoAttribute, UciMessage, UciMove, UciPiece, UciSquare, parse_one};
+use vampirc_uci::{
+    UciInfoAttribute, UciMessage, UciMove, UciOptionConfig, UciPiece, UciSquare, parse_one,
+};
 
 fn main() {
     // Read the engine name that was set at compile time.
@@ -70,20 +78,43 @@ fn main() {
     // --- Bot and its result channel ---
     let (result_tx, result_rx) = mpsc::channel::<BotMessage>();
     let mut bot = Bot::new(result_tx);
+    let mut search_config = SearchConfig::default();
+    // The bot searches on its own copy, this one is for the debug commands
+    let mut current_board = Board::default();
     loop {
         while let Ok(bot_message) = result_rx.try_recv() {
             match bot_message {
-                BotMessage::Info { depth, best_move } => {
+                BotMessage::Info(info) => {
+                    let score = match info.score {
+                        Score::Centipawns(cp) => UciInfoAttribute::from_centipawns(cp as i32),
+                        Score::Mate(moves) => UciInfoAttribute::from_mate(moves as i8),
+                    };
                     let uci_msg = UciMessage::Info(vec![
-                        UciInfoAttribute::Depth(depth),
-                        UciInfoAttribute::Pv(vec![string_to_uci_move(best_move.to_uci())]),
+                        UciInfoAttribute::Depth(info.depth),
+                        UciInfoAttribute::SelDepth(info.seldepth),
+                        score,
+                        UciInfoAttribute::Nodes(info.nodes),
+                        UciInfoAttribute::Nps(info.nodes_per_second),
+                        UciInfoAttribute::HashFull(info.hashfull),
+                        UciInfoAttribute::Time(Duration::from_std(info.time).unwrap()),
+                        UciInfoAttribute::Pv(
+                            info.pv
+                                .iter()
+                                .map(|m| string_to_uci_move(m.to_uci()))
+                                .collect(),
+                        ),
                     ]);
                     println!("{}", uci_msg);
                 }
                 BotMessage::BestMove(_move) => {
                     info!("Found best move: {}", _move.to_uci());
-                    let uci_message = UciMessage::best_move(string_to_uci_move(_move.to_uci()));
-                    writeln!(stdout, "{}", uci_message).unwrap();
+                    if _move == Move::null_move() {
+                        // There is no legal move, UCI writes the null move as 0000
+                        writeln!(stdout, "bestmove 0000").unwrap();
+                    } else {
+                        let uci_message = UciMessage::best_move(string_to_uci_move(_move.to_uci()));
+                        writeln!(stdout, "{}", uci_message).unwrap();
+                    }
                 }
             }
             stdout.flush().unwrap();
@@ -104,6 +135,9 @@ fn main() {
                         author: Some("lurchfresser".to_string()),
                     };
                     println!("{message}");
+                    for option in engine_options() {
+                        println!("{}", UciMessage::Option(option));
+                    }
                     let ok_meassage = UciMessage::UciOk;
 
                     println!("{ok_meassage}");
@@ -135,10 +169,62 @@ fn main() {
                     )
                     .unwrap();
 
+                    current_board = board;
                     bot.set_position(board, rep_look_up, resetting_moves as u8);
                 }
-                // UciMessage::SetOption { name, value } => todo!(),
-                // UciMessage::UciNewGame => todo!(),
+                UciMessage::SetOption { name, value } => match name.as_str() {
+                    "BookFile" => match value.as_deref() {
+                        None | Some("") | Some("<empty>") => bot.set_book(None),
+                        Some(path) => match PolyglotBook::from_file(path) {
+                            Ok(book) => {
+                                info!("Loaded book {} with {} entries", path, book.len());
+                                bot.set_book(Some(book));
+                            }
+                            Err(e) => {
+                                error!("Failed to load book {}: {}", path, e);
+                                println!("info string failed to load book {}: {}", path, e);
+                            }
+                        },
+                    },
+                    "BestBookMove" => {
+                        let selection = if value.as_deref() == Some("true") {
+                            BookSelection::BestWeight
+                        } else {
+                            BookSelection::WeightedRandom
+                        };
+                        bot.set_book_selection(selection);
+                    }
+                    "LateMoveReductions" => {
+                        search_config.late_move_reductions = value.as_deref() == Some("true");
+                        bot.set_search_config(search_config);
+                    }
+                    "FutilityPruning" => {
+                        search_config.futility_pruning = value.as_deref() == Some("true");
+                        bot.set_search_config(search_config);
+                    }
+                    "ReverseFutilityPruning" => {
+                        search_config.reverse_futility_pruning = value.as_deref() == Some("true");
+                        bot.set_search_config(search_config);
+                    }
+                    "Hash" => match value.as_deref().map(str::parse::<usize>) {
+                        Some(Ok(size_mb)) => bot.set_hash_size(size_mb.clamp(1, MAX_HASH_MB)),
+                        _ => error!("Invalid hash size: {:?}", value),
+                    },
+                    "MoveOverhead" => match value.as_deref().map(str::parse::<u64>) {
+                        Some(Ok(millis)) => bot.set_move_overhead(
+                            core::time::Duration::from_millis(millis.min(MAX_MOVE_OVERHEAD_MS)),
+                        ),
+                        _ => error!("Invalid move overhead: {:?}", value),
+                    },
+                    "Threads" => match value.as_deref().map(str::parse::<usize>) {
+                        Some(Ok(threads)) => bot.set_threads(threads.clamp(1, MAX_THREADS)),
+                        _ => error!("Invalid thread count: {:?}", value),
+                    },
+                    _ => info!("Unknown option: {}", name),
+                },
+                UciMessage::UciNewGame => {
+                    bot.new_game();
+                }
                 UciMessage::Stop => {
                     bot.stop();
                 }
@@ -158,7 +244,10 @@ fn main() {
                 // UciMessage::Registration(protection_state) => todo!(),
                 // UciMessage::Option(uci_option_config) => todo!(),
                 // UciMessage::Info(uci_info_attributes) => todo!(),
-                // UciMessage::Unknown(_, error) => todo!(),
+                // Not part of UCI, prints the evaluation of the current position term by term
+                UciMessage::Unknown(command, _) if command.trim() == "eval" => {
+                    println!("{}", eval_trace(&current_board));
+                }
                 _ => {}
             }
             stdout.flush().unwrap();
@@ -168,6 +257,53 @@ fn main() {
     info!("--- Shutting down ---");
 }
 
+const MAX_THREADS: usize = 256;
+const MAX_HASH_MB: usize = 65536;
+const MAX_MOVE_OVERHEAD_MS: u64 = 5000;
+
+fn engine_options() -> Vec<UciOptionConfig> {
+    vec![
+        UciOptionConfig::String {
+            name: "BookFile".to_string(),
+            default: Some("<empty>".to_string()),
+        },
+        UciOptionConfig::Check {
+            name: "BestBookMove".to_string(),
+            default: Some(false),
+        },
+        UciOptionConfig::Spin {
+            name: "Hash".to_string(),
+            default: Some(DEFAULT_SIZE_MB as i64),
+            min: Some(1),
+            max: Some(MAX_HASH_MB as i64),
+        },
+        UciOptionConfig::Spin {
+            name: "MoveOverhead".to_string(),
+            default: Some(DEFAULT_MOVE_OVERHEAD.as_millis() as i64),
+            min: Some(0),
+            max: Some(MAX_MOVE_OVERHEAD_MS as i64),
+        },
+        UciOptionConfig::Spin {
+            name: "Threads".to_string(),
+            default: Some(1),
+            min: Some(1),
+            max: Some(MAX_THREADS as i64),
+        },
+        UciOptionConfig::Check {
+            name: "LateMoveReductions".to_string(),
+            default: Some(SearchConfig::DEFAULT.late_move_reductions),
+        },
+        UciOptionConfig::Check {
+            name: "FutilityPruning".to_string(),
+            default: Some(SearchConfig::DEFAULT.futility_pruning),
+        },
+        UciOptionConfig::Check {
+            name: "ReverseFutilityPruning".to_string(),
+            default: Some(SearchConfig::DEFAULT.reverse_futility_pruning),
+        },
+    ]
+}
+
 fn string_to_uci_move(uci_string: String) -> UciMove {
     let from = UciSquare {
         file: uci_string.chars().next().unwrap(),
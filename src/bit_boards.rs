@@ -1,27 +1,9 @@
-#[macro_export]
-macro_rules! include_bytes_align_as {
-    ($align_ty:ty, $path:literal) => {{
-        use $crate::bit_boards::my_macros::AlignedAs;
-
-        static ALIGNED: &AlignedAs<$align_ty, [u8]> = &AlignedAs {
-            _align: [],
-            bytes: *include_bytes!($path),
-        };
-
-        &ALIGNED.bytes
-    }};
-}
-
-pub mod my_macros {
-    #[repr(C)]
-    pub struct AlignedAs<Align, Bytes: ?Sized> {
-        pub _align: [Align; 0],
-        pub bytes: Bytes,
-    }
-}
-
 use crate::const_move_gen::*;
+#[cfg(not(all(feature = "pext", target_arch = "x86_64")))]
+use crate::magics::{get_bishop_moves_magic, get_rook_moves_magic};
+#[cfg(all(feature = "pext", target_arch = "x86_64"))]
 use core::arch::x86_64::_pext_u64;
+#[cfg(all(feature = "pext", target_arch = "x86_64"))]
 use std::arch::x86_64::_mm_tzcnt_64;
 
 pub const FILE_A: u64 = 0x0101010101010101;
@@ -33,6 +15,10 @@ pub const FILE_F: u64 = 0x2020202020202020;
 pub const FILE_G: u64 = 0x4040404040404040;
 pub const FILE_H: u64 = 0x8080808080808080;
 
+pub const FILES: [u64; 8] = [
+    FILE_A, FILE_B, FILE_C, FILE_D, FILE_E, FILE_F, FILE_G, FILE_H,
+];
+
 pub const RANK_1: u64 = 0x00000000000000FF;
 pub const RANK_2: u64 = 0x000000000000FF00;
 pub const RANK_3: u64 = 0x0000000000FF0000;
@@ -89,23 +75,41 @@ pub static FREE_ROOK_LOOKUP: [u64; 64] = gen_free_rook_moves();
 pub static ROOK_SQUARE_TO_SQUARE_RAY_LOOKUP: [u64; 64 * 64] = gen_rook_square_to_square_ray();
 pub static FREE_KING_LOOKUP: [u64; 65] = gen_free_king_moves();
 
+pub static ADJACENT_FILES_LOOKUP: [u64; 8] = gen_adjacent_files();
+pub static WHITE_PASSED_PAWN_LOOKUP: [u64; 64] = gen_white_passed_pawn_masks();
+pub static BLACK_PASSED_PAWN_LOOKUP: [u64; 64] = gen_black_passed_pawn_masks();
+pub static WHITE_PAWN_SUPPORT_LOOKUP: [u64; 64] = gen_white_pawn_support_masks();
+pub static BLACK_PAWN_SUPPORT_LOOKUP: [u64; 64] = gen_black_pawn_support_masks();
+
 pub static HORIZONTALS_LOOKUP: [u64; 64] = gen_horizontal_rays();
 pub static VERTICALSS_LOOKUP: [u64; 64] = gen_vertical_rays();
 
 pub static NORTH_EAST_LOOKUP: [u64; 64] = gen_north_east_rays();
 pub static NORTH_WEST_LOOKUP: [u64; 64] = gen_north_west_rays();
 
+// Rook: 4 corners with 12 bits, 24 edge squares with 11 bits and 36 inner squares with 10 bits
+pub const ROOK_LOOK_UP_SIZE: usize = 102_400;
+pub const BISHOP_LOOK_UP_SIZE: usize = 5_248;
+
 pub static BISHOP_LOOKUP_MASK: [u64; 64] = gen_free_bishop_mask_edges_removed();
-static BISHOP_LOOKUP: &[u8] = include_bytes_align_as!(u64, "../assets/bishop_lookup.bin");
+pub static BISHOP_LOOKUP_OFFSETS: [usize; 64] =
+    gen_slider_lookup_offsets(&gen_free_bishop_mask_edges_removed());
+#[cfg(all(feature = "pext", target_arch = "x86_64"))]
+static BISHOP_LOOKUP: [u64; BISHOP_LOOK_UP_SIZE] = gen_pext_slider_lookup(
+    &gen_free_bishop_mask_edges_removed(),
+    &gen_slider_lookup_offsets(&gen_free_bishop_mask_edges_removed()),
+    &BISHOP_DIRECTIONS,
+);
 
 pub static ROOK_LOOKUP_MASK: [u64; 64] = gen_free_rook_mask_edges_removed();
-static ROOK_LOOKUP: &[u8] = include_bytes_align_as!(u64, "../assets/rook_lookup.bin");
-
-// TODO: rename or multiply by 64
-// If rook is in corner it is 2¹², if at edge 2¹¹ otherwise 2¹⁰
-// We get a lookup a bit too large, but that is ok
-pub const ROOK_LOOK_UP_SIZE: u64 = 1u64 << 12;
-pub const BISHOP_LOOK_UP_SIZE: u64 = 1u64 << 9;
+pub static ROOK_LOOKUP_OFFSETS: [usize; 64] =
+    gen_slider_lookup_offsets(&gen_free_rook_mask_edges_removed());
+#[cfg(all(feature = "pext", target_arch = "x86_64"))]
+static ROOK_LOOKUP: [u64; ROOK_LOOK_UP_SIZE] = gen_pext_slider_lookup(
+    &gen_free_rook_mask_edges_removed(),
+    &gen_slider_lookup_offsets(&gen_free_rook_mask_edges_removed()),
+    &ROOK_DIRECTIONS,
+);
 
 #[inline(always)]
 pub const fn square_index_to_bitboard(index: usize) -> u64 {
@@ -113,11 +117,18 @@ pub const fn square_index_to_bitboard(index: usize) -> u64 {
 }
 
 //TODO: measure different methods
+#[cfg(all(feature = "pext", target_arch = "x86_64"))]
 #[inline(always)]
 pub fn bitboard_to_square_index(bitboard: u64) -> usize {
     unsafe { _mm_tzcnt_64(bitboard) as usize }
 }
 
+#[cfg(not(all(feature = "pext", target_arch = "x86_64")))]
+#[inline(always)]
+pub fn bitboard_to_square_index(bitboard: u64) -> usize {
+    bitboard.trailing_zeros() as usize
+}
+
 #[inline(always)]
 pub const fn square_index_to_square(index: usize) -> Square {
     let file = (index % 8) as u64;
@@ -172,28 +183,32 @@ pub fn pop_lsb(bit_board: &mut u64) -> usize {
     trailing as usize
 }
 
+#[cfg(all(feature = "pext", target_arch = "x86_64"))]
 pub fn get_rook_moves(square: u32, blockers: u64) -> u64 {
     let rook_moves = ROOK_LOOKUP_MASK[square as usize];
-    let lookup_index = unsafe { _pext_u64(blockers, rook_moves) };
-
-    unsafe {
-        let ptr: *const u64 = ROOK_LOOKUP
-            .as_ptr()
-            .add(((square as u64) * 8u64 * ROOK_LOOK_UP_SIZE + lookup_index * 8u64) as usize)
-            as *const u64;
-        *ptr
-    }
+    let lookup_index = unsafe { _pext_u64(blockers, rook_moves) } as usize;
+
+    // the pext index is below 2^bits of the mask, so it stays within the square's entries
+    unsafe { *ROOK_LOOKUP.get_unchecked(ROOK_LOOKUP_OFFSETS[square as usize] + lookup_index) }
 }
 
+#[cfg(all(feature = "pext", target_arch = "x86_64"))]
 pub fn get_bishop_moves(square: usize, blockers: u64) -> u64 {
     let bishop_moves = BISHOP_LOOKUP_MASK[square];
-    let lookup_index = unsafe { _pext_u64(blockers, bishop_moves) };
-
-    unsafe {
-        let ptr: *const u64 = BISHOP_LOOKUP
-            .as_ptr()
-            .add(((square as u64) * 8u64 * BISHOP_LOOK_UP_SIZE + lookup_index * 8u64) as usize)
-            as *const u64;
-        *ptr
-    }
+    let lookup_index = unsafe { _pext_u64(blockers, bishop_moves) } as usize;
+
+    unsafe { *BISHOP_LOOKUP.get_unchecked(BISHOP_LOOKUP_OFFSETS[square] + lookup_index) }
+}
+
+// Without the pext feature (or on other architectures) fall back to magic bitboards.
+#[cfg(not(all(feature = "pext", target_arch = "x86_64")))]
+#[inline(always)]
+pub fn get_rook_moves(square: u32, blockers: u64) -> u64 {
+    get_rook_moves_magic(square as usize, blockers)
+}
+
+#[cfg(not(all(feature = "pext", target_arch = "x86_64")))]
+#[inline(always)]
+pub fn get_bishop_moves(square: usize, blockers: u64) -> u64 {
+    get_bishop_moves_magic(square, blockers)
 }
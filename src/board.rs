@@ -1,3 +1,4 @@
+use crate::eval::phase_weight;
 use crate::polyglot_zobrists::*;
 use crate::{bit_boards::*, moves::square_to_algebraic};
 use regex::Regex;
@@ -78,6 +79,20 @@ pub enum Piece {
     King { white: bool },
 }
 
+impl Piece {
+    pub fn kind(&self) -> Option<PieceKind> {
+        match self {
+            Piece::None => None,
+            Piece::Pawn { .. } => Some(PieceKind::Pawn),
+            Piece::Knight { .. } => Some(PieceKind::Knight),
+            Piece::Bishop { .. } => Some(PieceKind::Bishop),
+            Piece::Rook { .. } => Some(PieceKind::Rook),
+            Piece::Queen { .. } => Some(PieceKind::Queen),
+            Piece::King { .. } => Some(PieceKind::King),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Board {
     pub white_pawns: u64,
@@ -113,6 +128,26 @@ pub struct Board {
     pub pieces: [Piece; 64],
 
     pub zobrist_hash: u64,
+
+    /// A zobrist hash of the pawns only, to look up the pawn structure evaluation.
+    pub pawn_key: u64,
+
+    /// The game phase from the material left on the board, see `eval::phase_weight`.
+    /// It is kept up to date by `make_move`, just like `zobrist_hash`.
+    pub phase: u8,
+}
+
+/// Everything `unmake_move` needs that cannot be recovered from the move itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UndoInfo {
+    pub captured_piece: Piece,
+    pub en_passant_target: u64,
+    pub white_castling_rights: CastlingRights,
+    pub black_castling_rights: CastlingRights,
+    pub halfmove_clock: u8,
+    pub zobrist_hash: u64,
+    pub pawn_key: u64,
+    pub phase: u8,
 }
 
 #[derive(Debug, Clone)]
@@ -416,6 +451,8 @@ impl Board {
             full_move_number: fullmove_number,
             pieces,
             zobrist_hash,
+            pawn_key: pawn_key(white_pawns, black_pawns),
+            phase: pieces.iter().map(|&piece| phase_weight(piece)).sum(),
         })
     }
 
@@ -507,21 +544,10 @@ impl Board {
             fen.push_str(&castling_str);
         }
 
-        let lookup = if self.white_to_move {
-            &BLACK_FREE_PAWN_ADVANCE_LOOKUP
-        } else {
-            &WHITE_FREE_PAWN_ATTACKS_LOOKUP
-        };
-        let enemypawns = if self.white_to_move {
-            self.black_pawns
-        } else {
-            self.white_pawns
-        };
         // 4. En passant target square
+        // only set if a pawn can actually perform the capture, like in the Polyglot hash
         fen.push(' ');
-        if self.en_passant_target != 0
-            && lookup[bitboard_to_square_index(self.en_passant_target)] & enemypawns != 0
-        {
+        if self.en_passant_target != 0 {
             let ep_index = bitboard_to_square_index(self.en_passant_target);
             let ep_square = square_index_to_square(ep_index);
             fen.push_str(&square_to_algebraic(ep_square));
@@ -540,6 +566,15 @@ impl Board {
         fen
     }
 
+    /// Whether `white` has a piece other than pawns and the king.
+    pub fn has_non_pawn_material(&self, white: bool) -> bool {
+        if white {
+            self.white_knights | self.white_bishops | self.white_rooks | self.white_queens != 0
+        } else {
+            self.black_knights | self.black_bishops | self.black_rooks | self.black_queens != 0
+        }
+    }
+
     pub fn is_draw_by_insufficient_material(&self) -> bool {
         match self.all_pieces.count_ones() {
             //king vs king
@@ -564,6 +599,20 @@ impl Board {
     }
 }
 
+/// The zobrist hash of the pawns, maintained as `Board::pawn_key`.
+pub fn pawn_key(white_pawns: u64, black_pawns: u64) -> u64 {
+    let mut key = 0;
+    let mut pawns = white_pawns;
+    while pawns != 0 {
+        key ^= ZOBRISTS_WHITE_PAWNS[pop_lsb(&mut pawns)];
+    }
+    let mut pawns = black_pawns;
+    while pawns != 0 {
+        key ^= ZOBRISTS_BLACK_PAWNS[pop_lsb(&mut pawns)];
+    }
+    key
+}
+
 impl Default for Board {
     fn default() -> Self {
         // Initialize the board to the starting position
@@ -0,0 +1,312 @@
+use crate::board::{Board, PieceKind};
+use crate::moves::Move;
+use std::fmt;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Every Polyglot entry is 16 bytes: key (8), move (2), weight (2), learn (4), all big endian.
+pub const ENTRY_SIZE: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BookEntry {
+    pub key: u64,
+    pub raw_move: u16,
+    pub weight: u16,
+    pub learn: u32,
+}
+
+impl BookEntry {
+    pub fn from_bytes(bytes: &[u8; ENTRY_SIZE]) -> Self {
+        Self {
+            key: u64::from_be_bytes(bytes[0..8].try_into().unwrap()),
+            raw_move: u16::from_be_bytes(bytes[8..10].try_into().unwrap()),
+            weight: u16::from_be_bytes(bytes[10..12].try_into().unwrap()),
+            learn: u32::from_be_bytes(bytes[12..16].try_into().unwrap()),
+        }
+    }
+
+    pub fn to_bytes(&self) -> [u8; ENTRY_SIZE] {
+        let mut bytes = [0u8; ENTRY_SIZE];
+        bytes[0..8].copy_from_slice(&self.key.to_be_bytes());
+        bytes[8..10].copy_from_slice(&self.raw_move.to_be_bytes());
+        bytes[10..12].copy_from_slice(&self.weight.to_be_bytes());
+        bytes[12..16].copy_from_slice(&self.learn.to_be_bytes());
+        bytes
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BookSelection {
+    /// Always play the move with the highest weight.
+    BestWeight,
+    /// Pick a move with a probability proportional to its weight.
+    WeightedRandom,
+}
+
+#[derive(Debug)]
+pub enum BookError {
+    Io(std::io::Error),
+    InvalidLength(usize),
+}
+
+impl fmt::Display for BookError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BookError::Io(e) => write!(f, "Could not read book: {}", e),
+            BookError::InvalidLength(len) => write!(
+                f,
+                "Book size of {} bytes is not a multiple of {}",
+                len, ENTRY_SIZE
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BookError {}
+
+impl From<std::io::Error> for BookError {
+    fn from(e: std::io::Error) -> Self {
+        BookError::Io(e)
+    }
+}
+
+/// An in-memory Polyglot opening book.
+/// Our zobrist keys are Polyglot compatible, so `Board::zobrist_hash` can be used for lookups directly.
+#[derive(Debug)]
+pub struct PolyglotBook {
+    // sorted by key, as required by the Polyglot format
+    entries: Vec<BookEntry>,
+    rng_state: u64,
+}
+
+impl PolyglotBook {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, BookError> {
+        let bytes = std::fs::read(path)?;
+        Self::from_bytes(&bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BookError> {
+        if !bytes.len().is_multiple_of(ENTRY_SIZE) {
+            return Err(BookError::InvalidLength(bytes.len()));
+        }
+        let mut entries: Vec<BookEntry> = bytes
+            .chunks_exact(ENTRY_SIZE)
+            .map(|chunk| BookEntry::from_bytes(chunk.try_into().unwrap()))
+            .collect();
+        // books written by other tools should already be sorted, but binary search relies on it
+        entries.sort_by_key(|e| e.key);
+
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0);
+
+        Ok(Self {
+            entries,
+            // xorshift must never be seeded with 0
+            rng_state: seed | 1,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// All raw entries stored for a zobrist key.
+    pub fn entries(&self, key: u64) -> &[BookEntry] {
+        let start = self.entries.partition_point(|e| e.key < key);
+        let end = self.entries.partition_point(|e| e.key <= key);
+        &self.entries[start..end]
+    }
+
+    /// All book moves for this position that are legal, together with their weights.
+    pub fn moves(&self, board: &Board) -> Vec<(Move, u16)> {
+        self.entries(board.zobrist_hash)
+            .iter()
+            .filter_map(|e| decode_move(board, e.raw_move).map(|m| (m, e.weight)))
+            .collect()
+    }
+
+    pub fn choose_move(&mut self, board: &Board, selection: BookSelection) -> Option<Move> {
+        let moves = self.moves(board);
+        if moves.is_empty() {
+            return None;
+        }
+        match selection {
+            BookSelection::BestWeight => moves.iter().max_by_key(|(_, w)| *w).map(|(m, _)| *m),
+            BookSelection::WeightedRandom => {
+                let total_weight: u64 = moves.iter().map(|(_, w)| *w as u64).sum();
+                // a book may contain only zero weighted moves, then every move is equally likely
+                if total_weight == 0 {
+                    let index = (self.next_random() % moves.len() as u64) as usize;
+                    return Some(moves[index].0);
+                }
+                let mut pick = self.next_random() % total_weight;
+                for (m, weight) in &moves {
+                    if pick < *weight as u64 {
+                        return Some(*m);
+                    }
+                    pick -= *weight as u64;
+                }
+                unreachable!("pick is always smaller than the total weight")
+            }
+        }
+    }
+
+    fn next_random(&mut self) -> u64 {
+        // xorshift64, good enough for choosing opening moves
+        let mut x = self.rng_state;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.rng_state = x;
+        x
+    }
+}
+
+// [Bits 0-5] to square, [Bits 6-11] from square, [Bits 12-14] promotion piece
+const POLYGLOT_TO_MASK: u16 = 0b0000_0000_0011_1111;
+const POLYGLOT_FROM_SHIFT: u16 = 6;
+const POLYGLOT_FROM_MASK: u16 = 0b0000_1111_1100_0000;
+const POLYGLOT_PROMOTION_SHIFT: u16 = 12;
+const POLYGLOT_PROMOTION_MASK: u16 = 0b0111_0000_0000_0000;
+
+/// Decodes a Polyglot move into the matching legal move of `board`.
+/// Polyglot writes castling as "king takes own rook" (e1h1, e1a1, e8h8, e8a8).
+/// Returns `None` if the book move is not legal in this position, e.g. after a key collision.
+pub fn decode_move(board: &Board, raw_move: u16) -> Option<Move> {
+    let to = (raw_move & POLYGLOT_TO_MASK) as usize;
+    let from = ((raw_move & POLYGLOT_FROM_MASK) >> POLYGLOT_FROM_SHIFT) as usize;
+    let promotion = match (raw_move & POLYGLOT_PROMOTION_MASK) >> POLYGLOT_PROMOTION_SHIFT {
+        1 => Some(PieceKind::Knight),
+        2 => Some(PieceKind::Bishop),
+        3 => Some(PieceKind::Rook),
+        4 => Some(PieceKind::Queen),
+        _ => None,
+    };
+
+    board.generate_legal_moves_temp().into_iter().find(|m| {
+        if m.from() != from || m.promotion_piece() != promotion {
+            return false;
+        }
+        if m.is_castle_short() {
+            // king jumps to the g-file, rook sits on the h-file
+            to == m.to() + 1
+        } else if m.is_castle_long() {
+            // king jumps to the c-file, rook sits on the a-file
+            to + 2 == m.to()
+        } else {
+            to == m.to()
+        }
+    })
+}
+
+/// Encodes one of our moves in the Polyglot move format.
+pub fn encode_move(_move: &Move) -> u16 {
+    let from = _move.from() as u16;
+    let to = if _move.is_castle_short() {
+        _move.to() as u16 + 1
+    } else if _move.is_castle_long() {
+        _move.to() as u16 - 2
+    } else {
+        _move.to() as u16
+    };
+    let promotion = match _move.promotion_piece() {
+        Some(PieceKind::Knight) => 1,
+        Some(PieceKind::Bishop) => 2,
+        Some(PieceKind::Rook) => 3,
+        Some(PieceKind::Queen) => 4,
+        _ => 0,
+    };
+    to | (from << POLYGLOT_FROM_SHIFT) | (promotion << POLYGLOT_PROMOTION_SHIFT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // e2e4: from e2 (12), to e4 (28)
+    const E2E4: u16 = 28 | (12 << 6);
+    // d2d4: from d2 (11), to d4 (27)
+    const D2D4: u16 = 27 | (11 << 6);
+
+    fn book_bytes(entries: &[(u64, u16, u16)]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for (key, raw_move, weight) in entries {
+            bytes.extend_from_slice(&key.to_be_bytes());
+            bytes.extend_from_slice(&raw_move.to_be_bytes());
+            bytes.extend_from_slice(&weight.to_be_bytes());
+            bytes.extend_from_slice(&0u32.to_be_bytes());
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_lookup_start_position() {
+        let board = Board::default();
+        let bytes = book_bytes(&[
+            (1, E2E4, 5),
+            (board.zobrist_hash, E2E4, 10),
+            (board.zobrist_hash, D2D4, 3),
+            (u64::MAX, D2D4, 1),
+        ]);
+        let mut book = PolyglotBook::from_bytes(&bytes).unwrap();
+
+        let moves = book.moves(&board);
+        assert_eq!(moves.len(), 2);
+        assert_eq!(
+            book.choose_move(&board, BookSelection::BestWeight)
+                .unwrap()
+                .to_uci(),
+            "e2e4"
+        );
+        for _ in 0..20 {
+            let uci = book
+                .choose_move(&board, BookSelection::WeightedRandom)
+                .unwrap()
+                .to_uci();
+            assert!(uci == "e2e4" || uci == "d2d4");
+        }
+    }
+
+    #[test]
+    fn test_decode_castling() {
+        let board =
+            Board::from_fen("r3k2r/pppppppp/8/8/8/8/PPPPPPPP/R3K2R w KQkq - 0 1").unwrap();
+        // e1h1 and e1a1 in Polyglot notation
+        let short = decode_move(&board, 7 | (4 << 6)).unwrap();
+        let long = decode_move(&board, 4 << 6).unwrap();
+        assert!(short.is_castle_short());
+        assert!(long.is_castle_long());
+
+        let board =
+            Board::from_fen("r3k2r/pppppppp/8/8/8/8/PPPPPPPP/R3K2R b KQkq - 0 1").unwrap();
+        // e8h8 and e8a8
+        assert!(decode_move(&board, 63 | (60 << 6)).unwrap().is_castle_short());
+        assert!(decode_move(&board, 56 | (60 << 6)).unwrap().is_castle_long());
+    }
+
+    #[test]
+    fn test_invalid_length() {
+        assert!(PolyglotBook::from_bytes(&[0u8; 17]).is_err());
+    }
+
+    #[test]
+    fn test_encode_decode_roundtrip() {
+        let fens = [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b KQkq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        ];
+        for fen in fens {
+            let board = Board::from_fen(fen).unwrap();
+            for m in board.generate_legal_moves_temp() {
+                assert_eq!(decode_move(&board, encode_move(&m)), Some(m), "{}", fen);
+            }
+        }
+    }
+}
@@ -4,9 +4,11 @@ use std::{
         atomic::{AtomicBool, Ordering},
         mpsc::{self, Sender},
     },
-    thread::{self, sleep},
+    thread,
 };
 
+use crate::book::{BookSelection, PolyglotBook};
+use crate::time_manager::{DEFAULT_MOVE_OVERHEAD, TimeManager};
 use crate::tt_table::TT_Table;
 use crate::{board::*, moves::*, search::*};
 use core::time::Duration;
@@ -16,6 +18,13 @@ use core::time::Duration;
 pub enum BotCommand {
     SetBoard(Board, [u64; 100], u8),
     Search(SearchSpecs),
+    SetBook(Option<PolyglotBook>),
+    SetBookSelection(BookSelection),
+    SetSearchConfig(SearchConfig),
+    SetThreads(usize),
+    SetHashSize(usize),
+    SetMoveOverhead(Duration),
+    NewGame,
     Quit,
 }
 
@@ -44,10 +53,55 @@ pub enum SearchSpecs {
 // Protocol: Messages sent FROM the bot thread TO the main thread.
 #[derive(Debug)]
 pub enum BotMessage {
-    Info { best_move: Move, depth: u8 },
+    Info(SearchInfo),
     BestMove(Move),
 }
 
+/// What the bot reports after every finished iteration of its search.
+#[derive(Debug, Clone)]
+pub struct SearchInfo {
+    pub best_move: Move,
+    pub depth: u8,
+    /// The deepest ply reached, including quiescence search.
+    pub seldepth: u8,
+    pub score: Score,
+    pub pv: Vec<Move>,
+    /// The nodes searched by all threads.
+    pub nodes: u64,
+    pub nodes_per_second: u64,
+    /// How full the transposition table is, in permille.
+    pub hashfull: u16,
+    /// The time since the search was started.
+    pub time: Duration,
+}
+
+/// A score from the point of view of the side to move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Score {
+    Centipawns(i16),
+    /// Moves until mate, negative if the side to move gets mated.
+    Mate(i16),
+}
+
+impl Score {
+    fn from_search_score(score: i16, white_to_move: bool) -> Self {
+        let score = if white_to_move { score } else { -score };
+        match mate_in(score) {
+            Some(moves) => Score::Mate(moves),
+            None => Score::Centipawns(score),
+        }
+    }
+
+    /// The number of plies until mate, if this is a mate score.
+    fn mate_plies(self) -> Option<u16> {
+        match self {
+            Score::Mate(moves) if moves > 0 => Some(moves as u16 * 2 - 1),
+            Score::Mate(moves) => Some(moves.unsigned_abs() * 2),
+            Score::Centipawns(_) => None,
+        }
+    }
+}
+
 /// The public-facing Bot API.
 /// This is a lightweight handle that you interact with from your main thread.
 /// It just sends commands to the actual worker thread.
@@ -56,7 +110,6 @@ pub struct Bot {
     // The handle is optional, in case we want to join it on quit.
     thread_handle: Option<thread::JoinHandle<()>>,
     is_searching: Arc<AtomicBool>,
-    board: Board,
 }
 
 impl Bot {
@@ -75,7 +128,18 @@ impl Bot {
                         worker.set_position(board, repetition_lookup, num_resetting_moves)
                     }
                     BotCommand::Search(specs) => {
-                        worker.search();
+                        worker.search(specs);
+                    }
+                    BotCommand::SetBook(book) => worker.book = book,
+                    BotCommand::SetBookSelection(selection) => worker.book_selection = selection,
+                    BotCommand::SetSearchConfig(config) => worker.search_context.config = config,
+                    BotCommand::SetThreads(threads) => worker.num_threads = threads.max(1),
+                    BotCommand::SetHashSize(size_mb) => {
+                        worker.tt_table = TT_Table::with_size_mb(size_mb)
+                    }
+                    BotCommand::NewGame => worker.new_game(),
+                    BotCommand::SetMoveOverhead(move_overhead) => {
+                        worker.move_overhead = move_overhead
                     }
                     BotCommand::Quit => break, // Exit the loop and end the thread
                 }
@@ -86,7 +150,6 @@ impl Bot {
             command_tx,
             thread_handle: Some(thread_handle),
             is_searching,
-            board: Board::default()
         }
     }
 
@@ -97,7 +160,6 @@ impl Bot {
         repetition_lookup: [u64; 100],
         num_resetting_moves: u8,
     ) {
-        self.board = board;
         self.stop();
         self.command_tx
             .send(BotCommand::SetBoard(
@@ -108,56 +170,55 @@ impl Bot {
             .unwrap();
     }
 
+    /// Sets the opening book the bot consults before searching. `None` disables the book.
+    pub fn set_book(&self, book: Option<PolyglotBook>) {
+        self.command_tx.send(BotCommand::SetBook(book)).unwrap();
+    }
+
+    /// Sets how the bot picks a move if the book knows more than one.
+    pub fn set_book_selection(&self, selection: BookSelection) {
+        self.command_tx
+            .send(BotCommand::SetBookSelection(selection))
+            .unwrap();
+    }
+
+    /// Switches the selective search techniques on or off, e.g. to compare versions.
+    pub fn set_search_config(&self, config: SearchConfig) {
+        self.command_tx
+            .send(BotCommand::SetSearchConfig(config))
+            .unwrap();
+    }
+
+    /// Sets how many threads search in parallel. They share the transposition table,
+    /// only the result of the main thread is reported.
+    pub fn set_threads(&self, threads: usize) {
+        self.command_tx
+            .send(BotCommand::SetThreads(threads))
+            .unwrap();
+    }
+
+    /// Replaces the transposition table with an empty one of `size_mb` megabytes.
+    pub fn set_hash_size(&self, size_mb: usize) {
+        self.command_tx
+            .send(BotCommand::SetHashSize(size_mb))
+            .unwrap();
+    }
+
+    /// Forgets everything learned in the previous game.
+    pub fn new_game(&self) {
+        self.command_tx.send(BotCommand::NewGame).unwrap();
+    }
+
+    /// Sets how much time is kept back on every move for the communication with the GUI.
+    pub fn set_move_overhead(&self, move_overhead: Duration) {
+        self.command_tx
+            .send(BotCommand::SetMoveOverhead(move_overhead))
+            .unwrap();
+    }
+
     /// Tells the bot to start searching for the best move. This returns immediately.
     pub fn start_searching(&self, specs: SearchSpecs) {
-        match specs {
-            SearchSpecs::Infinite => {
-                self.command_tx
-                    .send(BotCommand::Search(SearchSpecs::Infinite))
-                    .unwrap();
-            }
-            SearchSpecs::TimeLeft {
-                white_time: white_time,
-                black_time: black_time,
-                white_increment: white_increment,
-                black_increment: black_increment,
-                moves_to_go: moves_to_go,
-            } => {
-                let my_time = if self.board.white_to_move {
-                    white_time
-                } else {
-                    black_time
-                };
-                if my_time.is_none() {
-                    return;
-                }
-                let my_increment = if self.board.white_to_move {
-                    white_increment
-                } else {
-                    black_increment
-                };
-                let move_time = (my_time.unwrap() / 40) + my_increment.unwrap_or(
-                    Duration::from_millis(0)
-                );
-
-                let is_searching = self.is_searching.clone();
-                set_time_out(move_time, move || {
-                    is_searching.store(false, Ordering::Relaxed);
-                });
-                self.command_tx
-                    .send(BotCommand::Search(SearchSpecs::Infinite))
-                    .unwrap();
-            }
-            SearchSpecs::MoveTime(move_time) => {
-                let is_searching = self.is_searching.clone();
-                set_time_out(move_time, move || {
-                    is_searching.store(false, Ordering::Relaxed);
-                });
-                self.command_tx
-                    .send(BotCommand::Search(SearchSpecs::Infinite))
-                    .unwrap();
-            }
-        };
+        self.command_tx.send(BotCommand::Search(specs)).unwrap();
     }
 
     /// Tells the bot to stop its current search.
@@ -178,11 +239,18 @@ impl Bot {
 struct BotWorker {
     board: Board,
     tt_table: TT_Table,
+    search_context: SearchContext,
+    // One search context for every helper thread, kept so their history survives between searches.
+    helper_contexts: Vec<SearchContext>,
+    num_threads: usize,
+    move_overhead: Duration,
     result_tx: Sender<BotMessage>,
     // This flag is essential for stopping the search gracefully.
     is_searching: Arc<AtomicBool>,
     repetition_lookup: [u64; 100],
     num_resetting_moves: u8,
+    book: Option<PolyglotBook>,
+    book_selection: BookSelection,
 }
 
 impl BotWorker {
@@ -190,10 +258,16 @@ impl BotWorker {
         Self {
             board: Board::default(),
             tt_table: TT_Table::new(),
+            search_context: SearchContext::new(),
+            helper_contexts: Vec::new(),
+            num_threads: 1,
+            move_overhead: DEFAULT_MOVE_OVERHEAD,
             result_tx,
             is_searching,
             repetition_lookup: [0; 100],
             num_resetting_moves: 0,
+            book: None,
+            book_selection: BookSelection::WeightedRandom,
         }
     }
 
@@ -208,61 +282,198 @@ impl BotWorker {
         self.num_resetting_moves = num_resetting_moves;
     }
 
+    fn new_game(&mut self) {
+        self.tt_table.clear();
+        let config = self.search_context.config;
+        self.search_context = SearchContext::new();
+        self.search_context.config = config;
+        self.helper_contexts.clear();
+    }
+
     /// The main search entry point, implementing iterative deepening.
-    fn search(&mut self) {
+    fn search(&mut self, specs: SearchSpecs) {
+        // No need to spend any time on the clock, if the book knows the position.
+        if let Some(book) = &mut self.book
+            && let Some(book_move) = book.choose_move(&self.board, self.book_selection)
+        {
+            self.result_tx
+                .send(BotMessage::BestMove(book_move))
+                .unwrap();
+            return;
+        }
+
         // Set the searching flag to true and clone it so the search function can check it.
         self.is_searching.store(true, Ordering::Relaxed);
-        let mut best_move_so_far = None;
-
-        // --- Iterative Deepening Loop ---
-        let mut depth = 0;
-        loop {
-            // Go up to a max depth
-            // Check if we were told to stop BEFORE starting the next depth.
-            if !self.is_searching.load(Ordering::Relaxed) {
-                break;
+        self.search_context.should_search = Arc::clone(&self.is_searching);
+        self.search_context.new_search();
+        self.tt_table.new_search();
+        // The metrics are global counters, helper threads would race on them.
+        let num_helpers = if cfg!(feature = "metrics") {
+            0
+        } else {
+            self.num_threads - 1
+        };
+        self.helper_contexts
+            .resize_with(num_helpers, SearchContext::new);
+        for helper_context in &mut self.helper_contexts {
+            helper_context.config = self.search_context.config;
+            helper_context.should_search = Arc::clone(&self.is_searching);
+            helper_context.new_search();
+        }
+        let mut time_manager =
+            TimeManager::new(specs, self.board.white_to_move, self.move_overhead);
+        self.search_context.deadline = time_manager.deadline();
+        // With only one legal move there is nothing to think about.
+        let is_forced_move = self.board.generate_legal_moves_temp().len() == 1;
+        let mut best_move_so_far: Option<SearchResult> = None;
+        let helper_nodes: Vec<_> = self
+            .helper_contexts
+            .iter()
+            .map(SearchContext::node_counter)
+            .collect();
+
+        thread::scope(|scope| {
+            // Lazy SMP: the helpers search the same position and only help the main thread
+            // through the shared transposition table. Half of them start one ply deeper,
+            // so the threads don't all search the same depth at the same time.
+            for (index, helper_context) in self.helper_contexts.iter_mut().enumerate() {
+                let board = self.board;
+                let repetition_lookup = self.repetition_lookup;
+                let num_resetting_moves = self.num_resetting_moves;
+                let tt_table = &self.tt_table;
+                scope.spawn(move || {
+                    search_helper(
+                        board,
+                        repetition_lookup,
+                        num_resetting_moves,
+                        1 + (index % 2) as u8,
+                        tt_table,
+                        helper_context,
+                    )
+                });
             }
 
-            // You'll need to adapt your search function to accept the stop flag.
-            let result = search_entry(
-                &self.board,
-                depth,
-                &mut self.tt_table,
-                &mut self.repetition_lookup,
-                self.num_resetting_moves,
-                &self.is_searching,
-            );
-
-            // If the search was stopped mid-way (result is None) or if there are no moves, break.
-            if let Some(best_move_at_depth) = result {
-                best_move_so_far = Some(best_move_at_depth);
-                // Send an 'info' message back to the main thread.
-                let info_msg = BotMessage::Info {
-                    best_move: best_move_at_depth,
+            // --- Iterative Deepening Loop ---
+            let mut depth = 0;
+            loop {
+                // Go up to a max depth
+                // Check if we were told to stop BEFORE starting the next depth.
+                if !self.is_searching.load(Ordering::Relaxed) || depth as usize >= MAX_PLY {
+                    break;
+                }
+
+                // You'll need to adapt your search function to accept the stop flag.
+                let result = search_entry(
+                    &mut self.board,
                     depth,
-                };
-                if let Err(e) = self.result_tx.send(info_msg) {
-                    panic!("{}", e)
+                    best_move_so_far.as_ref().map(|result| result.score),
+                    &self.tt_table,
+                    &mut self.search_context,
+                    &mut self.repetition_lookup,
+                    self.num_resetting_moves,
+                );
+
+                // If the search was stopped mid-way (result is None) or if there are no moves, break.
+                if let Some(result_at_depth) = result {
+                    // Send an 'info' message back to the main thread.
+                    let time = time_manager.elapsed();
+                    let nodes = self.search_context.nodes()
+                        + helper_nodes
+                            .iter()
+                            .map(|nodes| nodes.load(Ordering::Relaxed))
+                            .sum::<u64>();
+                    let score =
+                        Score::from_search_score(result_at_depth.score, self.board.white_to_move);
+                    let info_msg = BotMessage::Info(SearchInfo {
+                        best_move: result_at_depth.best_move,
+                        depth,
+                        seldepth: self.search_context.seldepth().min(u8::MAX as usize) as u8,
+                        score,
+                        pv: result_at_depth.pv.clone(),
+                        nodes,
+                        nodes_per_second: (nodes as f64 / time.as_secs_f64().max(0.001)) as u64,
+                        hashfull: self.tt_table.hashfull(),
+                        time,
+                    });
+                    let best_move_changed = best_move_so_far
+                        .as_ref()
+                        .is_some_and(|previous| previous.best_move != result_at_depth.best_move);
+                    best_move_so_far = Some(result_at_depth);
+                    if let Err(e) = self.result_tx.send(info_msg) {
+                        panic!("{}", e)
+                    }
+                    if !time_manager.is_infinite() && is_forced_move {
+                        break;
+                    }
+                    // Depth `depth` searches `depth + 1` plies, a mate within them can't get
+                    // any shorter by searching deeper
+                    if score
+                        .mate_plies()
+                        .is_some_and(|plies| plies <= depth as u16 + 1)
+                    {
+                        break;
+                    }
+                    if !time_manager.should_start_iteration(best_move_changed) {
+                        break;
+                    }
+                } else {
+                    // Search was stopped or completed without finding a better move
+                    break;
                 }
-            } else {
-                // Search was stopped or completed without finding a better move
-                break;
+                depth += 1;
             }
-            depth += 1;
-        }
 
-        // After the loop (or when stopped), send the best move found so far.
-        self.is_searching.store(false, Ordering::Relaxed);
+            // After the loop (or when stopped), stop the helpers as well.
+            self.is_searching.store(false, Ordering::Relaxed);
+        });
+
+        // Without a finished iteration, because there are no legal moves or the time ran out
+        // during the first one, the bot still has to answer. A null move means there is no move.
+        let best_move = best_move_so_far
+            .map(|result| result.best_move)
+            .or_else(|| self.fallback_move())
+            .unwrap_or(Move::null_move());
         self.result_tx
             //TODO: error handling
-            .send(BotMessage::BestMove(best_move_so_far.unwrap()))
+            .send(BotMessage::BestMove(best_move))
             .unwrap();
     }
+
+    /// The move from the transposition table if there is one, otherwise the first legal move.
+    fn fallback_move(&self) -> Option<Move> {
+        let legal_moves = self.board.generate_legal_moves_temp();
+        self.tt_table
+            .probe(self.board.zobrist_hash)
+            .and_then(|entry| entry.best_move())
+            .filter(|tt_move| legal_moves.contains(tt_move))
+            .or_else(|| legal_moves.first().copied())
+    }
 }
 
-pub fn set_time_out<F: Fn() + Send + 'static>(duration: Duration, c: F) {
-    thread::spawn(move || {
-        sleep(duration);
-        c();
-    });
+/// Iterative deepening for a helper thread, its results only end up in the transposition table.
+fn search_helper(
+    mut board: Board,
+    mut repetition_lookup: [u64; 100],
+    num_resetting_moves: u8,
+    start_depth: u8,
+    tt_table: &TT_Table,
+    search_context: &mut SearchContext,
+) {
+    let mut previous_score = None;
+    let mut depth = start_depth;
+    while search_context.should_search.load(Ordering::Relaxed) && (depth as usize) < MAX_PLY {
+        let Some(result) = search_entry(
+            &mut board,
+            depth,
+            previous_score,
+            tt_table,
+            search_context,
+            &mut repetition_lookup,
+            num_resetting_moves,
+        ) else {
+            break;
+        };
+        previous_score = Some(result.score);
+        depth += 1;
+    }
 }
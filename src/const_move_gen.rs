@@ -606,3 +606,144 @@ pub const fn gen_north_west_rays() -> [u64; 64] {
     }
     north_west_rays_lookup
 }
+
+pub const ROOK_DIRECTIONS: [(i32, i32); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+pub const BISHOP_DIRECTIONS: [(i32, i32); 4] = [(1, 1), (-1, 1), (1, -1), (-1, -1)];
+
+/// Walks every direction from `square` until the edge of the board or the first blocker,
+/// the blocker itself is included.
+pub const fn gen_slider_attacks(square: usize, blockers: u64, directions: &[(i32, i32); 4]) -> u64 {
+    let mut attacks = 0u64;
+    let mut d = 0;
+    while d < 4 {
+        let (file_step, rank_step) = directions[d];
+        let mut file = (square % 8) as i32 + file_step;
+        let mut rank = (square / 8) as i32 + rank_step;
+        while file >= 0 && file < 8 && rank >= 0 && rank < 8 {
+            let bit_board = 1u64 << (rank * 8 + file);
+            attacks |= bit_board;
+            if blockers & bit_board != 0 {
+                break;
+            }
+            file += file_step;
+            rank += rank_step;
+        }
+        d += 1;
+    }
+    attacks
+}
+
+/// Start of every square's entries in a slider lookup, each square gets `2^bits` entries
+/// where `bits` is the number of relevant blocker squares.
+pub const fn gen_slider_lookup_offsets(masks: &[u64; 64]) -> [usize; 64] {
+    let mut offsets = [0usize; 64];
+    let mut offset = 0;
+    let mut s = 0;
+    while s < 64 {
+        offsets[s] = offset;
+        offset += 1 << masks[s].count_ones();
+        s += 1;
+    }
+    offsets
+}
+
+/// Slider lookup indexed by `offsets[square] + pext(blockers, masks[square])`.
+pub const fn gen_pext_slider_lookup<const SIZE: usize>(
+    masks: &[u64; 64],
+    offsets: &[usize; 64],
+    directions: &[(i32, i32); 4],
+) -> [u64; SIZE] {
+    let mut lookup = [0u64; SIZE];
+    let mut s = 0;
+    while s < 64 {
+        let mask = masks[s];
+        // the carry-rippler visits the subsets of the mask in the order of their pext index
+        let mut blockers = 0u64;
+        let mut index = 0;
+        loop {
+            lookup[offsets[s] + index] = gen_slider_attacks(s, blockers, directions);
+            index += 1;
+            blockers = blockers.wrapping_sub(mask) & mask;
+            if blockers == 0 {
+                break;
+            }
+        }
+        s += 1;
+    }
+    lookup
+}
+
+/// The files next to each file.
+pub const fn gen_adjacent_files() -> [u64; 8] {
+    let mut adjacent_files = [0u64; 8];
+    let mut file = 0;
+    while file < 8 {
+        if file > 0 {
+            adjacent_files[file] |= FILE_A << (file - 1);
+        }
+        if file < 7 {
+            adjacent_files[file] |= FILE_A << (file + 1);
+        }
+        file += 1;
+    }
+    adjacent_files
+}
+
+// All squares on the ranks below `rank`
+const fn ranks_below(rank: usize) -> u64 {
+    if rank >= 8 {
+        u64::MAX
+    } else {
+        (1u64 << (rank * 8)) - 1
+    }
+}
+
+/// The squares in front of a white pawn on its own and the adjacent files.
+/// Without black pawns on them the pawn is passed.
+pub const fn gen_white_passed_pawn_masks() -> [u64; 64] {
+    let adjacent_files = gen_adjacent_files();
+    let mut masks = [0u64; 64];
+    let mut i = 0;
+    while i < 64 {
+        let files = (FILE_A << (i % 8)) | adjacent_files[i % 8];
+        masks[i] = files & !ranks_below(i / 8 + 1);
+        i += 1;
+    }
+    masks
+}
+
+pub const fn gen_black_passed_pawn_masks() -> [u64; 64] {
+    let adjacent_files = gen_adjacent_files();
+    let mut masks = [0u64; 64];
+    let mut i = 0;
+    while i < 64 {
+        let files = (FILE_A << (i % 8)) | adjacent_files[i % 8];
+        masks[i] = files & ranks_below(i / 8);
+        i += 1;
+    }
+    masks
+}
+
+/// The squares on the adjacent files on the rank of a white pawn and behind it,
+/// where the pawns are that can still advance to defend it.
+pub const fn gen_white_pawn_support_masks() -> [u64; 64] {
+    let adjacent_files = gen_adjacent_files();
+    let mut masks = [0u64; 64];
+    let mut i = 0;
+    while i < 64 {
+        masks[i] = adjacent_files[i % 8] & ranks_below(i / 8 + 1);
+        i += 1;
+    }
+    masks
+}
+
+pub const fn gen_black_pawn_support_masks() -> [u64; 64] {
+    let adjacent_files = gen_adjacent_files();
+    let mut masks = [0u64; 64];
+    let mut i = 0;
+    while i < 64 {
+        masks[i] = adjacent_files[i % 8] & !ranks_below(i / 8);
+        i += 1;
+    }
+    masks
+}
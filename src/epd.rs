@@ -0,0 +1,219 @@
+use crate::board::{Board, FenError};
+use crate::moves::Move;
+use crate::san::SanError;
+use std::fmt;
+
+#[derive(Debug, Clone)]
+pub enum EpdError {
+    MissingFields,
+    InvalidFen(FenError),
+    InvalidMove { opcode: String, error: SanError },
+    UnterminatedString,
+}
+
+impl fmt::Display for EpdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EpdError::MissingFields => write!(f, "EPD needs at least four position fields"),
+            EpdError::InvalidFen(e) => write!(f, "Invalid EPD position: {}", e),
+            EpdError::InvalidMove { opcode, error } => {
+                write!(f, "Invalid {} move: {}", opcode, error)
+            }
+            EpdError::UnterminatedString => write!(f, "String operand is never closed"),
+        }
+    }
+}
+
+impl std::error::Error for EpdError {}
+
+impl From<FenError> for EpdError {
+    fn from(e: FenError) -> Self {
+        EpdError::InvalidFen(e)
+    }
+}
+
+/// One line of an EPD file: a position followed by operations like `bm Qc7+; id "test 1";`.
+#[derive(Debug, Clone)]
+pub struct EpdPosition {
+    pub board: Board,
+    /// Every operation in file order, quotes around string operands are removed.
+    pub operations: Vec<(String, String)>,
+    /// Moves from the `bm` operation.
+    pub best_moves: Vec<Move>,
+    /// Moves from the `am` operation.
+    pub avoid_moves: Vec<Move>,
+}
+
+impl EpdPosition {
+    pub fn parse(line: &str) -> Result<Self, EpdError> {
+        let line = line.trim();
+        let mut fields = line.splitn(5, char::is_whitespace);
+        let position: Vec<&str> = fields.by_ref().take(4).collect();
+        if position.len() < 4 {
+            return Err(EpdError::MissingFields);
+        }
+        let operations = parse_operations(fields.next().unwrap_or(""))?;
+
+        // EPD has no clocks, unless they are given as operations
+        let operand = |opcode: &str| {
+            operations
+                .iter()
+                .find(|(op, _)| op == opcode)
+                .map(|(_, operand)| operand.as_str())
+        };
+        let fen = format!(
+            "{} {} {}",
+            position.join(" "),
+            operand("hmvc").unwrap_or("0"),
+            operand("fmvn").unwrap_or("1")
+        );
+        let board = Board::from_fen(&fen)?;
+
+        let parse_moves = |opcode: &str| -> Result<Vec<Move>, EpdError> {
+            operand(opcode)
+                .unwrap_or("")
+                .split_whitespace()
+                .map(|san| {
+                    board.parse_san(san).map_err(|error| EpdError::InvalidMove {
+                        opcode: opcode.to_string(),
+                        error,
+                    })
+                })
+                .collect()
+        };
+        let best_moves = parse_moves("bm")?;
+        let avoid_moves = parse_moves("am")?;
+
+        Ok(Self {
+            board,
+            operations,
+            best_moves,
+            avoid_moves,
+        })
+    }
+
+    /// Parses every non-empty line of an EPD file.
+    pub fn parse_all(epd: &str) -> Result<Vec<Self>, EpdError> {
+        epd.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(Self::parse)
+            .collect()
+    }
+
+    pub fn operation(&self, opcode: &str) -> Option<&str> {
+        self.operations
+            .iter()
+            .find(|(op, _)| op == opcode)
+            .map(|(_, operand)| operand.as_str())
+    }
+
+    pub fn id(&self) -> Option<&str> {
+        self.operation("id")
+    }
+
+    pub fn comment(&self) -> Option<&str> {
+        self.operation("c0")
+    }
+
+    /// A move solves the position if it is one of the best moves and none of the moves to avoid.
+    pub fn is_solved_by(&self, _move: &Move) -> bool {
+        (self.best_moves.is_empty() || self.best_moves.contains(_move))
+            && !self.avoid_moves.contains(_move)
+    }
+}
+
+fn parse_operations(operations: &str) -> Result<Vec<(String, String)>, EpdError> {
+    let mut parsed = Vec::new();
+    let mut current = String::new();
+    let mut in_string = false;
+
+    // semicolons inside of quoted strings do not end an operation
+    for c in operations.chars() {
+        match c {
+            '"' => {
+                in_string = !in_string;
+                current.push(c);
+            }
+            ';' if !in_string => {
+                if let Some(operation) = parse_operation(&current) {
+                    parsed.push(operation);
+                }
+                current.clear();
+            }
+            _ => current.push(c),
+        }
+    }
+    if in_string {
+        return Err(EpdError::UnterminatedString);
+    }
+    if let Some(operation) = parse_operation(&current) {
+        parsed.push(operation);
+    }
+    Ok(parsed)
+}
+
+fn parse_operation(operation: &str) -> Option<(String, String)> {
+    let operation = operation.trim();
+    if operation.is_empty() {
+        return None;
+    }
+    let (opcode, operand) = operation
+        .split_once(char::is_whitespace)
+        .unwrap_or((operation, ""));
+    let operand = operand.trim();
+    let operand = operand
+        .strip_prefix('"')
+        .and_then(|o| o.strip_suffix('"'))
+        .unwrap_or(operand);
+    Some((opcode.to_string(), operand.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_suite_line() {
+        let line = r#"1k2q3/8/4N3/8/2Q5/3K4/8/8 w - - bm Qc7+; id "Sergei.S.Markoff:Tablebase.Test.003"; c0 "win"; c1 "5-men";"#;
+        let position = EpdPosition::parse(line).unwrap();
+        assert_eq!(position.id(), Some("Sergei.S.Markoff:Tablebase.Test.003"));
+        assert_eq!(position.comment(), Some("win"));
+        assert_eq!(position.operation("c1"), Some("5-men"));
+        assert_eq!(position.best_moves.len(), 1);
+        assert_eq!(position.best_moves[0].to_uci(), "c4c7");
+        assert!(position.is_solved_by(&position.best_moves[0]));
+
+        let other = position.board.parse_san("Qc5").unwrap();
+        assert!(!position.is_solved_by(&other));
+    }
+
+    #[test]
+    fn test_avoid_moves_and_clocks() {
+        let line = r#"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - am f3 g4; id "a;b"; hmvc 3; fmvn 12;"#;
+        let position = EpdPosition::parse(line).unwrap();
+        assert_eq!(position.id(), Some("a;b"));
+        assert_eq!(position.board.halfmove_clock, 3);
+        assert_eq!(position.board.full_move_number, 12);
+        assert_eq!(position.avoid_moves.len(), 2);
+
+        let e4 = position.board.parse_san("e4").unwrap();
+        assert!(position.is_solved_by(&e4));
+        assert!(!position.is_solved_by(&position.avoid_moves[1]));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(matches!(
+            EpdPosition::parse("8/8/8/8 w"),
+            Err(EpdError::MissingFields)
+        ));
+        assert!(matches!(
+            EpdPosition::parse("4k3/8/8/8/8/8/8/4K3 w - - bm Qh5;"),
+            Err(EpdError::InvalidMove { .. })
+        ));
+        assert!(matches!(
+            EpdPosition::parse(r#"4k3/8/8/8/8/8/8/4K3 w - - id "open;"#),
+            Err(EpdError::UnterminatedString)
+        ));
+    }
+}
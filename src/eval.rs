@@ -1,5 +1,9 @@
-use crate::board::{Board, Piece};
+use crate::board::{Board, Piece, PieceKind};
+use crate::king_safety::{king_danger, king_safety};
 use crate::metrics::{SearchMetrics, TimingKind};
+use crate::pawn_structure::{PawnTable, side_pawn_structure};
+use crate::piece_terms::{piece_terms, side_piece_terms};
+use std::fmt;
 
 pub const PAWN_SCORE: i16 = 100;
 pub const KNIGHT_SCORE: i16 = 300;
@@ -7,6 +11,13 @@ pub const BISHOP_SCORE: i16 = 320;
 pub const ROOK_SCORE: i16 = 500;
 pub const QUEEN_SCORE: i16 = 900;
 
+/// The phase of the starting position, it goes down to 0 as pieces are traded.
+pub const MAX_PHASE: u8 = 24;
+
+// Material values in the middlegame and the endgame, indexed by `PieceKind`
+const MIDDLEGAME_VALUES: [i16; 6] = [82, 337, 365, 477, 1025, 0];
+const ENDGAME_VALUES: [i16; 6] = [94, 281, 297, 512, 936, 0];
+
 pub fn pieces_score(piece: Piece) -> i16 {
     match piece {
         Piece::None => 0,
@@ -49,45 +60,540 @@ pub fn pieces_score(piece: Piece) -> i16 {
     }
 }
 
-pub fn eval(board: &Board) -> i16 {
+// Piece-square tables from white's point of view, written as seen from white's side of the
+// board: the first row is the eighth rank. White looks them up with `square ^ 56`,
+// black with the square itself.
+#[rustfmt::skip]
+const MIDDLEGAME_TABLES: [[i16; 64]; 6] = [
+    // pawn
+    [
+          0,   0,   0,   0,   0,   0,   0,   0,
+         98, 134,  61,  95,  68, 126,  34, -11,
+         -6,   7,  26,  31,  65,  56,  25, -20,
+        -14,  13,   6,  21,  23,  12,  17, -23,
+        -27,  -2,  -5,  12,  17,   6,  10, -25,
+        -26,  -4,  -4, -10,   3,   3,  33, -12,
+        -35,  -1, -20, -23, -15,  24,  38, -22,
+          0,   0,   0,   0,   0,   0,   0,   0,
+    ],
+    // knight
+    [
+       -167, -89, -34, -49,  61, -97, -15,-107,
+        -73, -41,  72,  36,  23,  62,   7, -17,
+        -47,  60,  37,  65,  84, 129,  73,  44,
+         -9,  17,  19,  53,  37,  69,  18,  22,
+        -13,   4,  16,  13,  28,  19,  21,  -8,
+        -23,  -9,  12,  10,  19,  17,  25, -16,
+        -29, -53, -12,  -3,  -1,  18, -14, -19,
+       -105, -21, -58, -33, -17, -28, -19, -23,
+    ],
+    // bishop
+    [
+        -29,   4, -82, -37, -25, -42,   7,  -8,
+        -26,  16, -18, -13,  30,  59,  18, -47,
+        -16,  37,  43,  40,  35,  50,  37,  -2,
+         -4,   5,  19,  50,  37,  37,   7,  -2,
+         -6,  13,  13,  26,  34,  12,  10,   4,
+          0,  15,  15,  15,  14,  27,  18,  10,
+          4,  15,  16,   0,   7,  21,  33,   1,
+        -33,  -3, -14, -21, -13, -12, -39, -21,
+    ],
+    // rook
+    [
+         32,  42,  32,  51,  63,   9,  31,  43,
+         27,  32,  58,  62,  80,  67,  26,  44,
+         -5,  19,  26,  36,  17,  45,  61,  16,
+        -24, -11,   7,  26,  24,  35,  -8, -20,
+        -36, -26, -12,  -1,   9,  -7,   6, -23,
+        -45, -25, -16, -17,   3,   0,  -5, -33,
+        -44, -16, -20,  -9,  -1,  11,  -6, -71,
+        -19, -13,   1,  17,  16,   7, -37, -26,
+    ],
+    // queen
+    [
+        -28,   0,  29,  12,  59,  44,  43,  45,
+        -24, -39,  -5,   1, -16,  57,  28,  54,
+        -13, -17,   7,   8,  29,  56,  47,  57,
+        -27, -27, -16, -16,  -1,  17,  -2,   1,
+         -9, -26,  -9, -10,  -2,  -4,   3,  -3,
+        -14,   2, -11,  -2,  -5,   2,  14,   5,
+        -35,  -8,  11,   2,   8,  15,  -3,   1,
+         -1, -18,  -9,  10, -15, -25, -31, -50,
+    ],
+    // king
+    [
+        -65,  23,  16, -15, -56, -34,   2,  13,
+         29,  -1, -20,  -7,  -8,  -4, -38, -29,
+         -9,  24,   2, -16, -20,   6,  22, -22,
+        -17, -20, -12, -27, -30, -25, -14, -36,
+        -49,  -1, -27, -39, -46, -44, -33, -51,
+        -14, -14, -22, -46, -44, -30, -15, -27,
+          1,   7,  -8, -64, -43, -16,   9,   8,
+        -15,  36,  12, -54,   8, -28,  24,  14,
+    ],
+];
+
+#[rustfmt::skip]
+const ENDGAME_TABLES: [[i16; 64]; 6] = [
+    // pawn
+    [
+          0,   0,   0,   0,   0,   0,   0,   0,
+        178, 173, 158, 134, 147, 132, 165, 187,
+         94, 100,  85,  67,  56,  53,  82,  84,
+         32,  24,  13,   5,  -2,   4,  17,  17,
+         13,   9,  -3,  -7,  -7,  -8,   3,  -1,
+          4,   7,  -6,   1,   0,  -5,  -1,  -8,
+         13,   8,   8,  10,  13,   0,   2,  -7,
+          0,   0,   0,   0,   0,   0,   0,   0,
+    ],
+    // knight
+    [
+        -58, -38, -13, -28, -31, -27, -63, -99,
+        -25,  -8, -25,  -2,  -9, -25, -24, -52,
+        -24, -20,  10,   9,  -1,  -9, -19, -41,
+        -17,   3,  22,  22,  22,  11,   8, -18,
+        -18,  -6,  16,  25,  16,  17,   4, -18,
+        -23,  -3,  -1,  15,  10,  -3, -20, -22,
+        -42, -20, -10,  -5,  -2, -20, -23, -44,
+        -29, -51, -23, -15, -22, -18, -50, -64,
+    ],
+    // bishop
+    [
+        -14, -21, -11,  -8,  -7,  -9, -17, -24,
+         -8,  -4,   7, -12,  -3, -13,  -4, -14,
+          2,  -8,   0,  -1,  -2,   6,   0,   4,
+         -3,   9,  12,   9,  14,  10,   3,   2,
+         -6,   3,  13,  19,   7,  10,  -3,  -9,
+        -12,  -3,   8,  10,  13,   3,  -7, -15,
+        -14, -18,  -7,  -1,   4,  -9, -15, -27,
+        -23,  -9, -23,  -5,  -9, -16,  -5, -17,
+    ],
+    // rook
+    [
+         13,  10,  18,  15,  12,  12,   8,   5,
+         11,  13,  13,  11,  -3,   3,   8,   3,
+          7,   7,   7,   5,   4,  -3,  -5,  -3,
+          4,   3,  13,   1,   2,   1,  -1,   2,
+          3,   5,   8,   4,  -5,  -6,  -8, -11,
+         -4,   0,  -5,  -1,  -7, -12,  -8, -16,
+         -6,  -6,   0,   2,  -9,  -9, -11,  -3,
+         -9,   2,   3,  -1,  -5, -13,   4, -20,
+    ],
+    // queen
+    [
+         -9,  22,  22,  27,  27,  19,  10,  20,
+        -17,  20,  32,  41,  58,  25,  30,   0,
+        -20,   6,   9,  49,  47,  35,  19,   9,
+          3,  22,  24,  45,  57,  40,  57,  36,
+        -18,  28,  19,  47,  31,  34,  39,  23,
+        -16, -27,  15,   6,   9,  17,  10,   5,
+        -22, -23, -30, -16, -16, -23, -36, -32,
+        -33, -28, -22, -43,  -5, -32, -20, -41,
+    ],
+    // king
+    [
+        -74, -35, -18, -18, -11,  15,   4, -17,
+        -12,  17,  14,  17,  17,  38,  23,  11,
+         10,  17,  23,  15,  20,  45,  44,  13,
+         -8,  22,  24,  27,  26,  33,  26,   3,
+        -18,  -4,  21,  24,  27,  23,   9, -11,
+        -19,  -3,  11,  21,  23,  16,   7,  -9,
+        -27, -11,   4,  13,  14,   4,  -5, -17,
+        -53, -34, -21, -11, -28, -14, -24, -43,
+    ],
+];
+
+/// How much `piece` counts towards the game phase, only minor and major pieces do.
+pub fn phase_weight(piece: Piece) -> u8 {
+    match piece.kind() {
+        Some(PieceKind::Knight) | Some(PieceKind::Bishop) => 1,
+        Some(PieceKind::Rook) => 2,
+        Some(PieceKind::Queen) => 4,
+        _ => 0,
+    }
+}
+
+/// The squares attacked by the pieces of one side, grouped by how they move.
+pub struct AttackSquares {
+    pub pawns: u64,
+    pub knights: u64,
+    /// Bishops and queens
+    pub diagonal: u64,
+    /// Rooks and queens
+    pub orthogonal: u64,
+}
+
+impl AttackSquares {
+    pub fn of(board: &Board, white: bool) -> Self {
+        AttackSquares {
+            pawns: board.gen_pawn_attack_squares(white),
+            knights: board.generate_knight_attack_squares(white),
+            diagonal: board.generate_bishop_and_queen_attack_squares(white),
+            orthogonal: board.generate_rook_and_queen_attack_squares(white),
+        }
+    }
+
+    fn mobility(&self) -> i16 {
+        (self.pawns.count_ones()
+            + self.knights.count_ones()
+            + self.diagonal.count_ones()
+            + self.orthogonal.count_ones()) as i16
+    }
+}
+
+/// The weights of the evaluation terms, as (middlegame, endgame) scores per occurrence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EvalParams {
+    /// Per attacked square, in both phases.
+    pub mobility: i16,
+    pub bishop_pair: (i16, i16),
+    /// A rook on a file without pawns.
+    pub rook_open_file: (i16, i16),
+    /// A rook on a file with only enemy pawns.
+    pub rook_semi_open_file: (i16, i16),
+    /// A rook on the seventh rank, if there are enemy pawns on it or the enemy king is on the eighth.
+    pub rook_on_seventh: (i16, i16),
+    /// A knight in the enemy half defended by a pawn, that no enemy pawn can chase away.
+    pub knight_outpost: (i16, i16),
+    /// A bishop on a7 or h7 locked in by a pawn on b6 or g6.
+    pub trapped_bishop: (i16, i16),
+    /// A rook in the corner behind its king, after castling rights are lost.
+    pub trapped_rook: (i16, i16),
+}
+
+impl EvalParams {
+    pub const DEFAULT: Self = EvalParams {
+        mobility: 1,
+        bishop_pair: (30, 50),
+        rook_open_file: (25, 10),
+        rook_semi_open_file: (12, 6),
+        rook_on_seventh: (20, 30),
+        knight_outpost: (20, 10),
+        trapped_bishop: (-80, -80),
+        trapped_rook: (-40, -10),
+    };
+}
+
+impl Default for EvalParams {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+pub fn eval(board: &Board, params: &EvalParams, pawn_table: &mut PawnTable) -> i16 {
     SearchMetrics::change_timing_kind(TimingKind::Evaluation);
-    let score = board.score();
-    let mobility_score: i16 = board.gen_pawn_attack_squares(true).count_ones() as i16
-        - board.gen_pawn_attack_squares(false).count_ones() as i16
-        + board.generate_knight_attack_squares(true).count_ones() as i16
-        - board.generate_knight_attack_squares(false).count_ones() as i16
-        + board
-            .generate_bishop_and_queen_attack_squares(true)
-            .count_ones() as i16
-        - board
-            .generate_bishop_and_queen_attack_squares(false)
-            .count_ones() as i16
-        + board
-            .generate_rook_and_queen_attack_squares(true)
-            .count_ones() as i16
-        - board
-            .generate_rook_and_queen_attack_squares(false)
-            .count_ones() as i16;
-    score + (mobility_score as i16)
-}
-
-trait PiecesScore {
-    fn score(&self) -> i16;
-}
-
-impl PiecesScore for Board {
-    fn score(&self) -> i16 {
-        let mut score: i16 = 0;
-        score += (self.white_pawns.count_ones() as i16) * PAWN_SCORE;
-        score += (self.white_knights.count_ones() as i16) * KNIGHT_SCORE;
-        score += (self.white_bishops.count_ones() as i16) * BISHOP_SCORE;
-        score += (self.white_rooks.count_ones() as i16) * ROOK_SCORE;
-        score += (self.white_queens.count_ones() as i16) * QUEEN_SCORE;
-        score -= (self.black_pawns.count_ones() as i16) * PAWN_SCORE;
-        score -= (self.black_knights.count_ones() as i16) * KNIGHT_SCORE;
-        score -= (self.black_bishops.count_ones() as i16) * BISHOP_SCORE;
-        score -= (self.black_rooks.count_ones() as i16) * ROOK_SCORE;
-        score -= (self.black_queens.count_ones() as i16) * QUEEN_SCORE;
-        score
+    let (material_middlegame, material_endgame) = piece_square_scores(board);
+    let (pawns_middlegame, pawns_endgame) =
+        pawn_table.probe_or_evaluate(board.pawn_key, board.white_pawns, board.black_pawns);
+    let (pieces_middlegame, pieces_endgame) = piece_terms(board, params);
+    let middlegame = material_middlegame + pawns_middlegame as i32 + pieces_middlegame as i32;
+    let endgame = material_endgame + pawns_endgame as i32 + pieces_endgame as i32;
+
+    let white_attacks = AttackSquares::of(board, true);
+    let black_attacks = AttackSquares::of(board, false);
+    let mobility = white_attacks.mobility() - black_attacks.mobility();
+
+    taper(board.phase, middlegame, endgame)
+        + params.mobility * mobility
+        + king_safety(board, &white_attacks, &black_attacks)
+}
+
+/// Material and piece-square tables, as (middlegame, endgame) scores.
+fn piece_square_scores(board: &Board) -> (i32, i32) {
+    let (white_material, white_tables) = side_piece_squares(board, true);
+    let (black_material, black_tables) = side_piece_squares(board, false);
+    (
+        white_material.0 + white_tables.0 - black_material.0 - black_tables.0,
+        white_material.1 + white_tables.1 - black_material.1 - black_tables.1,
+    )
+}
+
+/// The material and the piece-square table bonus of one side, each as (middlegame, endgame).
+fn side_piece_squares(board: &Board, white: bool) -> ((i32, i32), (i32, i32)) {
+    let piece_boards = if white {
+        [
+            (PieceKind::Pawn, board.white_pawns),
+            (PieceKind::Knight, board.white_knights),
+            (PieceKind::Bishop, board.white_bishops),
+            (PieceKind::Rook, board.white_rooks),
+            (PieceKind::Queen, board.white_queens),
+            (PieceKind::King, board.white_king),
+        ]
+    } else {
+        [
+            (PieceKind::Pawn, board.black_pawns),
+            (PieceKind::Knight, board.black_knights),
+            (PieceKind::Bishop, board.black_bishops),
+            (PieceKind::Rook, board.black_rooks),
+            (PieceKind::Queen, board.black_queens),
+            (PieceKind::King, board.black_king),
+        ]
+    };
+    // The tables start at a8, so they are already mirrored for black
+    let flip = if white { 56 } else { 0 };
+    let mut material = (0, 0);
+    let mut tables = (0, 0);
+    for (kind, mut pieces) in piece_boards {
+        let kind = kind as usize;
+        while pieces != 0 {
+            let square = pieces.trailing_zeros() as usize ^ flip;
+            material.0 += MIDDLEGAME_VALUES[kind] as i32;
+            material.1 += ENDGAME_VALUES[kind] as i32;
+            tables.0 += MIDDLEGAME_TABLES[kind][square] as i32;
+            tables.1 += ENDGAME_TABLES[kind][square] as i32;
+            pieces &= pieces - 1;
+        }
+    }
+    (material, tables)
+}
+
+/// Blends the middlegame and the endgame score by the phase of the board.
+fn taper(phase: u8, middlegame: i32, endgame: i32) -> i16 {
+    // Promotions can push the phase past the starting position
+    let phase = phase.min(MAX_PHASE) as i32;
+    ((middlegame * phase + endgame * (MAX_PHASE as i32 - phase)) / MAX_PHASE as i32) as i16
+}
+
+/// The bonus of one evaluation term for each side, as (middlegame, endgame) scores.
+/// Terms that don't depend on the phase have the same middlegame and endgame score.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TermScores {
+    pub white: (i32, i32),
+    pub black: (i32, i32),
+}
+
+impl TermScores {
+    fn new(white: (i16, i16), black: (i16, i16)) -> Self {
+        TermScores {
+            white: (white.0 as i32, white.1 as i32),
+            black: (black.0 as i32, black.1 as i32),
+        }
+    }
+
+    fn untapered(white: i16, black: i16) -> Self {
+        Self::new((white, white), (black, black))
+    }
+
+    /// White's bonus minus black's, as (middlegame, endgame).
+    pub fn net(&self) -> (i32, i32) {
+        (self.white.0 - self.black.0, self.white.1 - self.black.1)
+    }
+}
+
+/// Every term of the evaluation of a position for each side, see `eval_trace`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EvalBreakdown {
+    pub phase: u8,
+    pub material: TermScores,
+    pub piece_squares: TermScores,
+    pub pawn_structure: TermScores,
+    pub pieces: TermScores,
+    pub mobility: TermScores,
+    pub king_safety: TermScores,
+}
+
+impl EvalBreakdown {
+    /// The terms that are blended by the phase.
+    fn tapered_terms(&self) -> [(&'static str, TermScores); 4] {
+        [
+            ("Material", self.material),
+            ("Piece squares", self.piece_squares),
+            ("Pawn structure", self.pawn_structure),
+            ("Pieces", self.pieces),
+        ]
+    }
+
+    /// The terms that are added to the blended score as they are.
+    fn untapered_terms(&self) -> [(&'static str, TermScores); 2] {
+        [
+            ("Mobility", self.mobility),
+            ("King safety", self.king_safety),
+        ]
+    }
+
+    /// The evaluation from white's point of view, the same score `eval` returns.
+    pub fn total(&self) -> i16 {
+        let (mut middlegame, mut endgame) = (0, 0);
+        for (_, term) in self.tapered_terms() {
+            let (term_middlegame, term_endgame) = term.net();
+            middlegame += term_middlegame;
+            endgame += term_endgame;
+        }
+        let untapered: i32 = self
+            .untapered_terms()
+            .iter()
+            .map(|(_, term)| term.net().0)
+            .sum();
+        taper(self.phase, middlegame, endgame) + untapered as i16
+    }
+}
+
+/// A table of every term. The total of each row is tapered on its own, so the rows can be off
+/// by rounding from the total at the bottom.
+impl fmt::Display for EvalBreakdown {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let line = "-".repeat(16 + 2 * (10 + 8) + 10);
+        writeln!(
+            f,
+            "{:<16}{:>10}{:>8}{:>10}{:>8}{:>10}",
+            "Term", "White MG", "EG", "Black MG", "EG", "Total"
+        )?;
+        writeln!(f, "{line}")?;
+        let terms = self
+            .tapered_terms()
+            .into_iter()
+            .map(|(name, term)| (name, term, true))
+            .chain(
+                self.untapered_terms()
+                    .into_iter()
+                    .map(|(name, term)| (name, term, false)),
+            );
+        for (name, term, tapered) in terms {
+            let (middlegame, endgame) = term.net();
+            let total = if tapered {
+                taper(self.phase, middlegame, endgame)
+            } else {
+                middlegame as i16
+            };
+            writeln!(
+                f,
+                "{:<16}{:>10}{:>8}{:>10}{:>8}{:>10}",
+                name, term.white.0, term.white.1, term.black.0, term.black.1, total
+            )?;
+        }
+        writeln!(f, "{line}")?;
+        write!(
+            f,
+            "{:<16}{:>46}",
+            format!("Phase {}/{}", self.phase.min(MAX_PHASE), MAX_PHASE),
+            self.total()
+        )
+    }
+}
+
+/// Breaks the evaluation of `board` with the default weights down into its terms, to see why
+/// `eval` likes a position. The total of the breakdown is exactly the score of `eval`.
+pub fn eval_trace(board: &Board) -> EvalBreakdown {
+    let params = EvalParams::DEFAULT;
+    let (white_material, white_tables) = side_piece_squares(board, true);
+    let (black_material, black_tables) = side_piece_squares(board, false);
+    let white_attacks = AttackSquares::of(board, true);
+    let black_attacks = AttackSquares::of(board, false);
+
+    EvalBreakdown {
+        phase: board.phase,
+        material: TermScores {
+            white: white_material,
+            black: black_material,
+        },
+        piece_squares: TermScores {
+            white: white_tables,
+            black: black_tables,
+        },
+        pawn_structure: TermScores::new(
+            side_pawn_structure(board.white_pawns, board.black_pawns, true),
+            side_pawn_structure(board.black_pawns, board.white_pawns, false),
+        ),
+        pieces: TermScores::new(
+            side_piece_terms(board, true, &params),
+            side_piece_terms(board, false, &params),
+        ),
+        mobility: TermScores::untapered(
+            params.mobility * white_attacks.mobility(),
+            params.mobility * black_attacks.mobility(),
+        ),
+        king_safety: TermScores::untapered(
+            -king_danger(board, true, &black_attacks),
+            -king_danger(board, false, &white_attacks),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The same position with the colors swapped.
+    fn mirror_fen(fen: &str) -> String {
+        let mut parts = fen.split_whitespace();
+        let placement: Vec<String> = parts
+            .next()
+            .unwrap()
+            .split('/')
+            .rev()
+            .map(|rank| {
+                rank.chars()
+                    .map(|c| {
+                        if c.is_ascii_uppercase() {
+                            c.to_ascii_lowercase()
+                        } else {
+                            c.to_ascii_uppercase()
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        let side = if parts.next() == Some("w") { "b" } else { "w" };
+        format!("{} {} - - 0 1", placement.join("/"), side)
+    }
+
+    #[test]
+    fn test_eval_is_symmetric() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1",
+            "r1bq1rk1/pp2bppp/2n1pn2/3p4/2PP4/2N1PN2/PP3PPP/R2QKB1R w - - 0 1",
+            "8/5pk1/6p1/8/3R4/6P1/5PKP/2r5 b - - 0 1",
+            "2r2rk1/pB3ppp/1p2p3/3N4/4P3/8/PP3PPP/5K1R w - - 0 1",
+        ] {
+            let board = Board::from_fen(fen).unwrap();
+            let mirrored = Board::from_fen(&mirror_fen(fen)).unwrap();
+            let mut pawn_table = PawnTable::new();
+            let params = EvalParams::DEFAULT;
+            assert_eq!(
+                eval(&board, &params, &mut pawn_table),
+                -eval(&mirrored, &params, &mut pawn_table),
+                "{}",
+                fen
+            );
+        }
+    }
+
+    #[test]
+    fn test_eval_trace_total() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1",
+            "r1bq1rk1/pp2bppp/2n1pn2/3p4/2PP4/2N1PN2/PP3PPP/R2QKB1R w - - 0 1",
+            "2r2rk1/pB3ppp/1p2p3/3N4/4P3/8/PP3PPP/5K1R w - - 0 1",
+            "r4rk1/ppp2p1p/8/8/8/6q1/PPP2P1P/R2Q1RK1 w - - 0 1",
+            "8/5pk1/6p1/8/3R4/6P1/5PKP/2r5 b - - 0 1",
+            "4k3/1P6/8/8/8/8/8/4K3 w - - 0 1",
+        ] {
+            let board = Board::from_fen(fen).unwrap();
+            let trace = eval_trace(&board);
+            let score = eval(&board, &EvalParams::DEFAULT, &mut PawnTable::new());
+            assert_eq!(trace.total(), score, "{}", fen);
+            assert!(trace.to_string().ends_with(&score.to_string()), "{}", trace);
+        }
+    }
+
+    #[test]
+    fn test_phase() {
+        let board = Board::default();
+        assert_eq!(board.phase, MAX_PHASE);
+        let board = Board::from_fen("8/5pk1/6p1/8/3R4/6P1/5PKP/2r5 b - - 0 1").unwrap();
+        assert_eq!(board.phase, 4);
+
+        // kept up to date by make_move and restored by unmake_move
+        let mut board = Board::from_fen("r3k3/1P6/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let promotion = board
+            .generate_legal_moves_temp()
+            .iter()
+            .copied()
+            .find(|m| m.to_uci() == "b7a8q")
+            .unwrap();
+        let undo_info = board.make_move(promotion);
+        assert_eq!(board.phase, 4);
+        board.unmake_move(promotion, undo_info);
+        assert_eq!(board.phase, 2);
     }
 }
@@ -0,0 +1,146 @@
+use crate::bit_boards::*;
+use crate::board::Board;
+use crate::eval::AttackSquares;
+
+// Penalty for a file next to the king, by the distance of the nearest own pawn in front of it,
+// the last entry is for no pawn at all
+const PAWN_SHIELD: [i16; 4] = [0, 0, 10, 25];
+// Penalty for an enemy pawn in front of the king, by its distance
+const PAWN_STORM: [i16; 4] = [0, 10, 20, 10];
+// Extra penalty for a file next to the king without any pawns
+const OPEN_FILE: i16 = 15;
+
+// How much an attack on a square next to the king counts, by the kind of attacker
+const PAWN_ATTACK_WEIGHT: i16 = 1;
+const KNIGHT_ATTACK_WEIGHT: i16 = 2;
+const DIAGONAL_ATTACK_WEIGHT: i16 = 2;
+const ORTHOGONAL_ATTACK_WEIGHT: i16 = 3;
+// The penalty grows with the square of the attack weight, up to this
+const MAX_ATTACK_PENALTY: i16 = 400;
+
+// Non-pawn material in phase units, with all pieces on the board
+const FULL_ATTACKING_MATERIAL: i16 = 12;
+
+/// King safety from white's point of view: pawn shield, pawn storm, open files and attacks
+/// next to each king. The danger for a king is scaled by the material its opponent has left
+/// to attack it with.
+pub fn king_safety(
+    board: &Board,
+    white_attacks: &AttackSquares,
+    black_attacks: &AttackSquares,
+) -> i16 {
+    king_danger(board, false, white_attacks) - king_danger(board, true, black_attacks)
+}
+
+/// The danger for the king of one side, as a penalty for that side.
+pub fn king_danger(board: &Board, white: bool, enemy_attacks: &AttackSquares) -> i16 {
+    let (king, own_pawns, enemy_pawns) = if white {
+        (board.white_king, board.white_pawns, board.black_pawns)
+    } else {
+        (board.black_king, board.black_pawns, board.white_pawns)
+    };
+    let king_square = bitboard_to_square_index(king);
+    let king_file = king_square % 8;
+    let in_front = if white {
+        WHITE_PASSED_PAWN_LOOKUP[king_square]
+    } else {
+        BLACK_PASSED_PAWN_LOOKUP[king_square]
+    };
+
+    let mut danger = 0;
+    for &file in &FILES[king_file.saturating_sub(1)..=(king_file + 1).min(7)] {
+        let file_in_front = in_front & file;
+        let shield = own_pawns & file_in_front;
+        let storm = enemy_pawns & file_in_front;
+
+        let shield_distance = pawn_distance(shield, king_square, white).unwrap_or(3);
+        danger += PAWN_SHIELD[shield_distance.min(3)];
+        if let Some(distance) = pawn_distance(storm, king_square, white)
+            && distance < PAWN_STORM.len()
+        {
+            danger += PAWN_STORM[distance];
+        }
+        if (own_pawns | enemy_pawns) & file == 0 {
+            danger += OPEN_FILE;
+        }
+    }
+
+    let king_zone = FREE_KING_LOOKUP[king_square];
+    let attacks_on_zone = |attacks: u64| (attacks & king_zone).count_ones() as i16;
+    let attack_weight = attacks_on_zone(enemy_attacks.pawns) * PAWN_ATTACK_WEIGHT
+        + attacks_on_zone(enemy_attacks.knights) * KNIGHT_ATTACK_WEIGHT
+        + attacks_on_zone(enemy_attacks.diagonal) * DIAGONAL_ATTACK_WEIGHT
+        + attacks_on_zone(enemy_attacks.orthogonal) * ORTHOGONAL_ATTACK_WEIGHT;
+    danger += (attack_weight * attack_weight).min(MAX_ATTACK_PENALTY);
+
+    danger * attacking_material(board, !white) / FULL_ATTACKING_MATERIAL
+}
+
+/// The number of ranks between the king and the nearest of `pawns` in front of it.
+fn pawn_distance(pawns: u64, king_square: usize, white: bool) -> Option<usize> {
+    if pawns == 0 {
+        return None;
+    }
+    let nearest = if white {
+        bitboard_to_square_index(pawns)
+    } else {
+        63 - pawns.leading_zeros() as usize
+    };
+    Some((nearest / 8).abs_diff(king_square / 8))
+}
+
+/// The non-pawn material of one side in phase units, at most `FULL_ATTACKING_MATERIAL`.
+fn attacking_material(board: &Board, white: bool) -> i16 {
+    let (knights, bishops, rooks, queens) = if white {
+        (
+            board.white_knights,
+            board.white_bishops,
+            board.white_rooks,
+            board.white_queens,
+        )
+    } else {
+        (
+            board.black_knights,
+            board.black_bishops,
+            board.black_rooks,
+            board.black_queens,
+        )
+    };
+    let material =
+        (knights | bishops).count_ones() + rooks.count_ones() * 2 + queens.count_ones() * 4;
+    (material as i16).min(FULL_ATTACKING_MATERIAL)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn white_king_danger(fen: &str) -> i16 {
+        let board = Board::from_fen(fen).unwrap();
+        king_danger(&board, true, &AttackSquares::of(&board, false))
+    }
+
+    #[test]
+    fn test_king_danger() {
+        let castled = white_king_danger("r2q1rk1/ppp2ppp/8/8/8/8/PPP2PPP/R2Q1RK1 w - - 0 1");
+        assert_eq!(castled, 0);
+
+        // pushing the shield pawns weakens the king
+        let pushed = white_king_danger("r2q1rk1/ppp2ppp/8/8/8/6PP/PPP2P2/R2Q1RK1 w - - 0 1");
+        assert!(pushed > castled);
+
+        // an open file next to it is worse
+        let open_file = white_king_danger("r2q1rk1/ppp2p1p/8/8/8/8/PPP2P1P/R2Q1RK1 w - - 0 1");
+        assert!(open_file > pushed);
+
+        // and even worse if the queen attacks the king
+        let attacked = white_king_danger("r4rk1/ppp2p1p/8/8/8/6q1/PPP2P1P/R2Q1RK1 w - - 0 1");
+        assert!(attacked > open_file);
+
+        // without the enemy pieces the same king is safe
+        assert_eq!(
+            white_king_danger("6k1/ppp2p1p/8/8/8/8/PPP2P1P/R2Q1RK1 w - - 0 1"),
+            0
+        );
+    }
+}
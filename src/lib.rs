@@ -1,4 +1,5 @@
 pub mod bit_boards;
+pub mod magics;
 pub mod board;
 pub mod const_move_gen;
 pub mod eval;
@@ -9,3 +10,13 @@ pub mod search;
 pub mod polyglot_zobrists;
 pub mod bot;
 pub mod tt_table;
+pub mod book;
+pub mod pgn;
+pub mod san;
+pub mod epd;
+pub mod perft;
+pub mod see;
+pub mod pawn_structure;
+pub mod king_safety;
+pub mod piece_terms;
+pub mod time_manager;
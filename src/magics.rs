@@ -0,0 +1,281 @@
+//! Magic bitboard lookups for sliding pieces, used when PEXT (BMI2) is not available.
+//! `(blockers & mask) * magic >> shift` maps every blocker subset of a square to a unique index.
+
+use crate::bit_boards::{BISHOP_LOOK_UP_SIZE, ROOK_LOOK_UP_SIZE};
+use crate::const_move_gen::{
+    BISHOP_DIRECTIONS, ROOK_DIRECTIONS, gen_free_bishop_mask_edges_removed,
+    gen_free_rook_mask_edges_removed, gen_slider_attacks,
+};
+
+// Found with a random search over sparse numbers, using the smallest possible shift per square.
+const ROOK_MAGIC_NUMBERS: [u64; 64] = [
+    0x0980008011400020,
+    0x8340004410002000,
+    0x0880200090008268,
+    0x0080080080100004,
+    0x8100110004020800,
+    0x0300010004000822,
+    0x08801A0029000080,
+    0x8100050001204882,
+    0x0844800081400320,
+    0x0804402010004000,
+    0x0108802003100480,
+    0x0004808008001000,
+    0x0003001801001014,
+    0x0002000200041008,
+    0x0004008108042210,
+    0x0105000100009042,
+    0x0400808000400021,
+    0xC100404010002000,
+    0x0060008010002088,
+    0x0400808008001000,
+    0x4440808008000400,
+    0x1002008004000280,
+    0x40024400300D1248,
+    0x0010020000408104,
+    0x0101008200204200,
+    0x8020002040005000,
+    0x4100100080802000,
+    0x4008006A80100280,
+    0x1020080080040080,
+    0x0004010040020040,
+    0x0018A12400080290,
+    0x6140004200008104,
+    0x4000400020800090,
+    0x2020002080804000,
+    0x0000408202002010,
+    0x0080100501000820,
+    0x0000800400800800,
+    0x000A200408014010,
+    0x0100800200800100,
+    0xA00800570200008C,
+    0x008000406000C010,
+    0x1040100028002000,
+    0x0048200100110040,
+    0x0068490210030020,
+    0x1009080005010010,
+    0x2142000804010100,
+    0x1001080110840002,
+    0x1801004400820001,
+    0x010440208D020200,
+    0x0000400020008080,
+    0x0200200080100280,
+    0x0000100020090100,
+    0x0204008008020480,
+    0x8104010040020040,
+    0x78000201B0080400,
+    0x0040800051002880,
+    0x0050108001002041,
+    0x208A801100614003,
+    0x0006002042089082,
+    0x0011090004201001,
+    0x1002001004200802,
+    0x0005000208040001,
+    0x0002002701AC0822,
+    0x000010250184004A,
+];
+const BISHOP_MAGIC_NUMBERS: [u64; 64] = [
+    0xC0A0012206040EA0,
+    0x8010228200420001,
+    0x0110008220400400,
+    0x02445C0080106000,
+    0x0044042004008100,
+    0x0880900420408C05,
+    0x0201080110080002,
+    0x0000108094202000,
+    0x0000042002040108,
+    0x0000623024110042,
+    0x0086100094811002,
+    0x0000044502002080,
+    0x0100460211400040,
+    0x0008109004200004,
+    0x0202320084844000,
+    0x8040042421041009,
+    0x201010C05102008C,
+    0x1020888208024080,
+    0x0108000C80290200,
+    0x8048000420425203,
+    0x0005000090402000,
+    0x2080400201104100,
+    0x8820420111101000,
+    0x4AC0302208821802,
+    0x000440001002A840,
+    0x2002200010041080,
+    0x1012080201004400,
+    0x8440040002410120,
+    0x1090820084010400,
+    0x2084852012021000,
+    0x12040062C1011003,
+    0x02008205E1090080,
+    0x088C102808042080,
+    0x0802102200904280,
+    0x8020209002080020,
+    0x2200080800060A00,
+    0x20C0004010010100,
+    0x0802004100821003,
+    0x0008024400008080,
+    0x0000840102008090,
+    0x0030A40420244007,
+    0x0A19084210011282,
+    0x0004082090019806,
+    0x6108004208020080,
+    0x0081200410110100,
+    0x1040810701010208,
+    0x0282047832012080,
+    0x0010020099000020,
+    0x000E010422400840,
+    0x10204208B0089090,
+    0x081004440C048000,
+    0x88C0180084040001,
+    0x3100020803040080,
+    0x890070A041210C00,
+    0x0020200101010A09,
+    0x0004100240410400,
+    0x0006004402080200,
+    0x0801062484042000,
+    0x00010002D7441004,
+    0x0810080000208800,
+    0x0000020808030411,
+    0x1450001020014440,
+    0x004060081081A288,
+    0x0044011404108A00,
+];
+
+#[derive(Debug, Clone, Copy)]
+pub struct Magic {
+    pub mask: u64,
+    pub magic: u64,
+    pub shift: u32,
+    /// Start of this square's entries in the attack table.
+    pub offset: usize,
+}
+
+impl Magic {
+    #[inline(always)]
+    pub const fn index(&self, blockers: u64) -> usize {
+        self.offset + ((blockers & self.mask).wrapping_mul(self.magic) >> self.shift) as usize
+    }
+}
+
+pub static ROOK_MAGICS: [Magic; 64] =
+    gen_magics(&gen_free_rook_mask_edges_removed(), &ROOK_MAGIC_NUMBERS);
+pub static BISHOP_MAGICS: [Magic; 64] =
+    gen_magics(&gen_free_bishop_mask_edges_removed(), &BISHOP_MAGIC_NUMBERS);
+
+static ROOK_MAGIC_ATTACKS: [u64; ROOK_LOOK_UP_SIZE] =
+    gen_magic_attacks(&ROOK_MAGICS, &ROOK_DIRECTIONS);
+static BISHOP_MAGIC_ATTACKS: [u64; BISHOP_LOOK_UP_SIZE] =
+    gen_magic_attacks(&BISHOP_MAGICS, &BISHOP_DIRECTIONS);
+
+#[inline(always)]
+pub fn get_rook_moves_magic(square: usize, blockers: u64) -> u64 {
+    ROOK_MAGIC_ATTACKS[ROOK_MAGICS[square].index(blockers)]
+}
+
+#[inline(always)]
+pub fn get_bishop_moves_magic(square: usize, blockers: u64) -> u64 {
+    BISHOP_MAGIC_ATTACKS[BISHOP_MAGICS[square].index(blockers)]
+}
+
+const fn gen_magics(masks: &[u64; 64], magic_numbers: &[u64; 64]) -> [Magic; 64] {
+    let mut magics = [Magic {
+        mask: 0,
+        magic: 0,
+        shift: 0,
+        offset: 0,
+    }; 64];
+    let mut offset = 0;
+    let mut s = 0;
+    while s < 64 {
+        let bits = masks[s].count_ones();
+        magics[s] = Magic {
+            mask: masks[s],
+            magic: magic_numbers[s],
+            shift: 64 - bits,
+            offset,
+        };
+        offset += 1 << bits;
+        s += 1;
+    }
+    magics
+}
+
+const fn gen_magic_attacks<const SIZE: usize>(
+    magics: &[Magic; 64],
+    directions: &[(i32, i32); 4],
+) -> [u64; SIZE] {
+    let mut attacks = [0u64; SIZE];
+    let mut s = 0;
+    while s < 64 {
+        let mask = magics[s].mask;
+        // enumerate all subsets of the mask (carry-rippler)
+        let mut blockers = 0u64;
+        loop {
+            attacks[magics[s].index(blockers)] = gen_slider_attacks(s, blockers, directions);
+            blockers = blockers.wrapping_sub(mask) & mask;
+            if blockers == 0 {
+                break;
+            }
+        }
+        s += 1;
+    }
+    attacks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bit_boards::{get_bishop_moves, get_rook_moves};
+
+    /// Every blocker subset of the relevance mask, plus a random pattern outside of it.
+    fn blocker_sets(mask: u64, square: usize) -> Vec<u64> {
+        let noise = 0x9E37_79B9_7F4A_7C15u64.rotate_left(square as u32) & !mask;
+        let mut sets = Vec::new();
+        let mut blockers = 0u64;
+        loop {
+            sets.push(blockers);
+            sets.push(blockers | noise);
+            blockers = blockers.wrapping_sub(mask) & mask;
+            if blockers == 0 {
+                break;
+            }
+        }
+        sets
+    }
+
+    #[test]
+    fn test_magic_table_sizes() {
+        let last_rook = ROOK_MAGICS[63];
+        let last_bishop = BISHOP_MAGICS[63];
+        assert_eq!(
+            last_rook.offset + (1 << (64 - last_rook.shift)),
+            ROOK_LOOK_UP_SIZE
+        );
+        assert_eq!(
+            last_bishop.offset + (1 << (64 - last_bishop.shift)),
+            BISHOP_LOOK_UP_SIZE
+        );
+    }
+
+    #[test]
+    fn test_rook_magics_match_lookup() {
+        for square in 0..64 {
+            for blockers in blocker_sets(ROOK_MAGICS[square].mask, square) {
+                let expected = gen_slider_attacks(square, blockers, &ROOK_DIRECTIONS);
+                assert_eq!(get_rook_moves_magic(square, blockers), expected);
+                assert_eq!(get_rook_moves(square as u32, blockers), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_bishop_magics_match_lookup() {
+        for square in 0..64 {
+            for blockers in blocker_sets(BISHOP_MAGICS[square].mask, square) {
+                let expected = gen_slider_attacks(square, blockers, &BISHOP_DIRECTIONS);
+                assert_eq!(get_bishop_moves_magic(square, blockers), expected);
+                assert_eq!(get_bishop_moves(square, blockers), expected);
+            }
+        }
+    }
+}
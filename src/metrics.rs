@@ -23,6 +23,14 @@ pub struct SearchMetricsData {
     pub stand_pat_cutoffs: u64,
     pub normal_search_cutoffs: u64,
     pub q_search_cutoffs: u64,
+    pub null_move_cutoffs: u64,
+    pub pvs_re_searches: u64,
+    pub aspiration_re_searches: u64,
+    pub late_move_reductions: u64,
+    pub late_move_re_searches: u64,
+    pub futility_pruned_moves: u64,
+    pub reverse_futility_cutoffs: u64,
+    pub check_extensions: u64,
 
     // --- Move Ordering Quality Metrics (already split or specific) ---
     pub normal_search_best_move_first_count: u64,
@@ -35,9 +43,7 @@ pub struct SearchMetricsData {
     pub q_search_sum_of_cutoff_indices: u64,
 
     // pub Note: These typically only apply to normal search, so we can leave them as is.
-    #[serde(skip_serializing)]
     pub killer_move_cutoffs: u64,
-    #[serde(skip_serializing)]
     pub history_heuristic_cutoffs: u64,
 
     // --- Split TT Metrics ---
@@ -97,6 +103,14 @@ impl SearchMetricsData {
             stand_pat_cutoffs: 0,
             normal_search_cutoffs: 0,
             q_search_cutoffs: 0,
+            null_move_cutoffs: 0,
+            pvs_re_searches: 0,
+            aspiration_re_searches: 0,
+            late_move_reductions: 0,
+            late_move_re_searches: 0,
+            futility_pruned_moves: 0,
+            reverse_futility_cutoffs: 0,
+            check_extensions: 0,
 
             normal_search_best_move_first_count: 0,
             q_search_best_move_first_count: 0,
@@ -284,6 +298,94 @@ impl SearchMetrics {
     #[cfg(not(feature = "metrics"))]
     pub fn increment_q_search_cutoffs() {}
 
+    #[cfg(feature = "metrics")]
+    pub fn increment_null_move_cutoffs() {
+        unsafe {
+            if let Some(m) = &mut METRICS {
+                m.null_move_cutoffs += 1;
+            }
+        }
+    }
+    #[cfg(not(feature = "metrics"))]
+    pub fn increment_null_move_cutoffs() {}
+
+    #[cfg(feature = "metrics")]
+    pub fn increment_pvs_re_searches() {
+        unsafe {
+            if let Some(m) = &mut METRICS {
+                m.pvs_re_searches += 1;
+            }
+        }
+    }
+    #[cfg(not(feature = "metrics"))]
+    pub fn increment_pvs_re_searches() {}
+
+    #[cfg(feature = "metrics")]
+    pub fn increment_aspiration_re_searches() {
+        unsafe {
+            if let Some(m) = &mut METRICS {
+                m.aspiration_re_searches += 1;
+            }
+        }
+    }
+    #[cfg(not(feature = "metrics"))]
+    pub fn increment_aspiration_re_searches() {}
+
+    #[cfg(feature = "metrics")]
+    pub fn increment_late_move_reductions() {
+        unsafe {
+            if let Some(m) = &mut METRICS {
+                m.late_move_reductions += 1;
+            }
+        }
+    }
+    #[cfg(not(feature = "metrics"))]
+    pub fn increment_late_move_reductions() {}
+
+    #[cfg(feature = "metrics")]
+    pub fn increment_late_move_re_searches() {
+        unsafe {
+            if let Some(m) = &mut METRICS {
+                m.late_move_re_searches += 1;
+            }
+        }
+    }
+    #[cfg(not(feature = "metrics"))]
+    pub fn increment_late_move_re_searches() {}
+
+    #[cfg(feature = "metrics")]
+    pub fn increment_futility_pruned_moves() {
+        unsafe {
+            if let Some(m) = &mut METRICS {
+                m.futility_pruned_moves += 1;
+            }
+        }
+    }
+    #[cfg(not(feature = "metrics"))]
+    pub fn increment_futility_pruned_moves() {}
+
+    #[cfg(feature = "metrics")]
+    pub fn increment_reverse_futility_cutoffs() {
+        unsafe {
+            if let Some(m) = &mut METRICS {
+                m.reverse_futility_cutoffs += 1;
+            }
+        }
+    }
+    #[cfg(not(feature = "metrics"))]
+    pub fn increment_reverse_futility_cutoffs() {}
+
+    #[cfg(feature = "metrics")]
+    pub fn increment_check_extensions() {
+        unsafe {
+            if let Some(m) = &mut METRICS {
+                m.check_extensions += 1;
+            }
+        }
+    }
+    #[cfg(not(feature = "metrics"))]
+    pub fn increment_check_extensions() {}
+
     // --- Cutoff Index Sums ---
 
     #[cfg(feature = "metrics")]
@@ -1,5 +1,6 @@
 use crate::bit_boards::*;
 use crate::board::*;
+use crate::eval::phase_weight;
 use crate::moves::*;
 use crate::polyglot_zobrists::*;
 
@@ -108,6 +109,18 @@ impl Board {
             &BLACK_FREE_PAWN_ATTACKS_LOOKUP
         };
 
+        // en passant also stops a check by the pawn that just double pushed
+        let en_passant_captured_pawn = if self.white_to_move {
+            self.en_passant_target >> 8
+        } else {
+            self.en_passant_target << 8
+        };
+        let to_mask = if en_passant_captured_pawn & to_mask != 0 {
+            to_mask | self.en_passant_target
+        } else {
+            to_mask
+        };
+
         while pawns != 0 {
             let mut moves_for_pawn = 0u64;
             let pawn_index = pop_lsb(&mut pawns);
@@ -731,440 +744,289 @@ impl Board {
         panic!("uci move: {} not found", uci_move);
     }
 
+    /// Copy-make version of `make_move`, the board itself is left untouched.
     pub fn make_move_temp(&self, _move: &Move) -> Self {
         let mut new_board = *self;
-        new_board.en_passant_target = 0;
-
-        if self.en_passant_target != 0 {
-            let ep_square_index = bitboard_to_square_index(self.en_passant_target);
-            let ep_file = ep_square_index % 8;
-            //undo prev ep_target from zobrist hash
-            new_board.zobrist_hash ^= ZOBRISTS_EN_PASSANT_FILE[ep_file];
-        }
+        new_board.make_move(*_move);
+        new_board
+    }
 
+    /// Plays a legal move in place. The returned `UndoInfo` has to be passed to `unmake_move`
+    /// together with the same move to restore the board.
+    pub fn make_move(&mut self, _move: Move) -> UndoInfo {
         let from = _move.from();
         let to = _move.to();
-        let from_bb = square_index_to_bitboard(from);
-        let to_bb = square_index_to_bitboard(to);
-        let move_mask = from_bb | to_bb;
-
+        let white = self.white_to_move;
         let moved_piece = self.pieces[from];
 
-        if _move.is_castle_short() {
-            new_board.pieces[from] = Piece::None;
-            if new_board.white_to_move {
-                // Update castling rights in hash
-                new_board.zobrist_hash ^= self.white_castling_rights.zobrist_hash(true);
-                new_board.white_castling_rights = CastlingRights::None;
-
-                // Remove king from original position and add to new position in hash
-                new_board.zobrist_hash ^= ZOBRISTS_WHITE_KINGS[from]; // Remove king from E1
-                new_board.zobrist_hash ^= ZOBRISTS_WHITE_KINGS[WHITE_KINGSIDE_CASTLE_INDEX]; // Add king to G1
-
-                // Remove rook from original position and add to new position in hash
-                new_board.zobrist_hash ^= ZOBRISTS_WHITE_ROOKS[WHITE_KINGSIDE_CASTLE_ROOK_INDEX]; // Remove rook from H1
-                new_board.zobrist_hash ^=
-                    ZOBRISTS_WHITE_ROOKS[WHITE_KINGSIDE_CASTLE_ROOK_INDEX - 2]; // Add rook to F1
-
-                new_board.white_king = WHITE_KINGSIDE_CASTLE_KING_BIT_BOARD;
-                new_board.white_rooks ^= WHITE_KINGSIDE_CASTLE_ROOK_MASK;
-                new_board.pieces[WHITE_KINGSIDE_CASTLE_ROOK_INDEX] = Piece::None;
-                new_board.pieces[WHITE_KINGSIDE_CASTLE_ROOK_INDEX - 2] =
-                    Piece::Rook { white: true };
-                new_board.pieces[WHITE_KINGSIDE_CASTLE_INDEX] = Piece::King { white: true };
-            } else {
-                // Update castling rights in hash
-                new_board.zobrist_hash ^= self.black_castling_rights.zobrist_hash(false);
-                new_board.black_castling_rights = CastlingRights::None;
-
-                // Remove king from original position and add to new position in hash
-                new_board.zobrist_hash ^= ZOBRISTS_BLACK_KINGS[from]; // Remove king from E8
-                new_board.zobrist_hash ^= ZOBRISTS_BLACK_KINGS[BLACK_KINGSIDE_CASTLE_INDEX]; // Add king to G8
-
-                // Remove rook from original position and add to new position in hash
-                new_board.zobrist_hash ^= ZOBRISTS_BLACK_ROOKS[BLACK_KINGSIDE_CASTLE_ROOK_INDEX]; // Remove rook from H8
-                new_board.zobrist_hash ^=
-                    ZOBRISTS_BLACK_ROOKS[BLACK_KINGSIDE_CASTLE_ROOK_INDEX - 2]; // Add rook to F8
-
-                new_board.black_king = BLACK_KINGSIDE_CASTLE_KING_BIT_BOARD;
-                new_board.black_rooks ^= BLACK_KINGSIDE_CASTLE_ROOK_MASK;
-                new_board.pieces[BLACK_KINGSIDE_CASTLE_ROOK_INDEX] = Piece::None;
-                new_board.pieces[BLACK_KINGSIDE_CASTLE_ROOK_INDEX - 2] =
-                    Piece::Rook { white: false };
-                new_board.pieces[BLACK_KINGSIDE_CASTLE_INDEX] = Piece::King { white: false };
-            }
-            new_board.recompute_combined_bit_boards();
-            new_board.update_board_state(false, false);
+        let captured_square = captured_square(&_move, white);
+        let captured_piece = if _move.is_capture() {
+            self.pieces[captured_square]
+        } else {
+            Piece::None
+        };
 
-            return new_board;
-        } else if _move.is_castle_long() {
-            new_board.pieces[from] = Piece::None;
-            if new_board.white_to_move {
-                // Update castling rights in hash
-                new_board.zobrist_hash ^= self.white_castling_rights.zobrist_hash(true);
-                new_board.white_castling_rights = CastlingRights::None;
-
-                // Remove king from original position and add to new position in hash
-                new_board.zobrist_hash ^= ZOBRISTS_WHITE_KINGS[from]; // Remove king from E1
-                new_board.zobrist_hash ^= ZOBRISTS_WHITE_KINGS[WHITE_QUEENSIDE_CASTLE_INDEX]; // Add king to C1
-
-                // Remove rook from original position and add to new position in hash
-                new_board.zobrist_hash ^= ZOBRISTS_WHITE_ROOKS[WHITE_QUEENSIDE_CASTLE_ROOK_INDEX]; // Remove rook from A1
-                new_board.zobrist_hash ^=
-                    ZOBRISTS_WHITE_ROOKS[WHITE_QUEENSIDE_CASTLE_ROOK_INDEX + 3]; // Add rook to D1
-
-                new_board.white_king = WHITE_QUEENSIDE_CASTLE_KING_BIT_BOARD;
-                new_board.white_rooks ^= WHITE_QUEENSIDE_CASTLE_ROOK_MASK;
-                new_board.pieces[WHITE_QUEENSIDE_CASTLE_ROOK_INDEX] = Piece::None;
-                new_board.pieces[WHITE_QUEENSIDE_CASTLE_ROOK_INDEX + 3] =
-                    Piece::Rook { white: true };
-                new_board.pieces[WHITE_QUEENSIDE_CASTLE_INDEX] = Piece::King { white: true };
-            } else {
-                // Update castling rights in hash
-                new_board.zobrist_hash ^= self.black_castling_rights.zobrist_hash(false);
-                new_board.black_castling_rights = CastlingRights::None;
-
-                // Remove king from original position and add to new position in hash
-                new_board.zobrist_hash ^= ZOBRISTS_BLACK_KINGS[from]; // Remove king from E8
-                new_board.zobrist_hash ^= ZOBRISTS_BLACK_KINGS[BLACK_QUEENSIDE_CASTLE_INDEX]; // Add king to C8
-
-                // Remove rook from original position and add to new position in hash
-                new_board.zobrist_hash ^= ZOBRISTS_BLACK_ROOKS[BLACK_QUEENSIDE_CASTLE_ROOK_INDEX]; // Remove rook from A8
-                new_board.zobrist_hash ^=
-                    ZOBRISTS_BLACK_ROOKS[BLACK_QUEENSIDE_CASTLE_ROOK_INDEX + 3]; // Add rook to D8
-
-                new_board.black_king = BLACK_QUEENSIDE_CASTLE_KING_BIT_BOARD;
-                new_board.black_rooks ^= BLACK_QUEENSIDE_CASTLE_ROOK_MASK;
-                new_board.pieces[BLACK_QUEENSIDE_CASTLE_ROOK_INDEX] = Piece::None;
-                new_board.pieces[BLACK_QUEENSIDE_CASTLE_ROOK_INDEX + 3] =
-                    Piece::Rook { white: false };
-                new_board.pieces[BLACK_QUEENSIDE_CASTLE_INDEX] = Piece::King { white: false };
-            }
-            new_board.recompute_combined_bit_boards();
-            new_board.update_board_state(false, false);
+        let undo_info = UndoInfo {
+            captured_piece,
+            en_passant_target: self.en_passant_target,
+            white_castling_rights: self.white_castling_rights,
+            black_castling_rights: self.black_castling_rights,
+            halfmove_clock: self.halfmove_clock,
+            zobrist_hash: self.zobrist_hash,
+            pawn_key: self.pawn_key,
+            phase: self.phase,
+        };
 
-            return new_board;
+        if self.en_passant_target != 0 {
+            //undo prev ep_target from zobrist hash
+            let ep_file = bitboard_to_square_index(self.en_passant_target) % 8;
+            self.zobrist_hash ^= ZOBRISTS_EN_PASSANT_FILE[ep_file];
+            self.en_passant_target = 0;
+        }
+        match captured_piece {
+            Piece::None => {}
+            Piece::King { .. } => panic!("Cannot capture a king"),
+            _ => self.remove_piece(captured_square, captured_piece),
         }
 
-        if _move.is_capture() {
-            let mut captured_piece = new_board.pieces[to];
-            // Additional logic for en-passant capture
-            let captured_piece_bb = if _move.is_en_passant() {
-                if new_board.white_to_move {
-                    captured_piece = new_board.pieces[to + 8];
-                    to_bb >> 8
-                } else {
-                    captured_piece = new_board.pieces[to - 8];
-                    to_bb << 8
-                }
-            } else {
-                to_bb
-            };
+        if moved_piece == Piece::None {
+            panic!(
+                "make_move tried to move a piece from an empty square: {}",
+                _move.to_uci()
+            );
+        }
+        self.remove_piece(from, moved_piece);
+        let placed_piece = match _move.promotion_piece() {
+            Some(piece_kind) => promoted_piece(piece_kind, white),
+            None => moved_piece,
+        };
+        self.add_piece(to, placed_piece);
 
-            match captured_piece {
-                Piece::Pawn { .. } => {
-                    if new_board.white_to_move {
-                        new_board.black_pawns ^= captured_piece_bb;
-                        // Remove black pawn from hash
-                        if _move.is_en_passant() {
-                            new_board.zobrist_hash ^= ZOBRISTS_BLACK_PAWNS[to + 8];
-                        } else {
-                            new_board.zobrist_hash ^= ZOBRISTS_BLACK_PAWNS[to];
-                        }
-                    } else {
-                        new_board.white_pawns ^= captured_piece_bb;
-                        // Remove white pawn from hash
-                        if _move.is_en_passant() {
-                            new_board.zobrist_hash ^= ZOBRISTS_WHITE_PAWNS[to - 8];
-                        } else {
-                            new_board.zobrist_hash ^= ZOBRISTS_WHITE_PAWNS[to];
-                        }
-                    }
-                }
-                Piece::Knight { .. } => {
-                    if new_board.white_to_move {
-                        new_board.black_knights ^= captured_piece_bb;
-                        new_board.zobrist_hash ^= ZOBRISTS_BLACK_KNIGHTS[to];
-                    } else {
-                        new_board.white_knights ^= captured_piece_bb;
-                        new_board.zobrist_hash ^= ZOBRISTS_WHITE_KNIGHTS[to];
-                    }
+        if _move.is_castle() {
+            let (rook_from, rook_to) = castling_rook_squares(&_move, white);
+            let rook = Piece::Rook { white };
+            self.remove_piece(rook_from, rook);
+            self.add_piece(rook_to, rook);
+        }
+
+        // Update castling rights if the king moves or a rook is moved or captured
+        let is_king_move = matches!(moved_piece, Piece::King { .. });
+        let touches_rook_corner =
+            (square_index_to_bitboard(from) | square_index_to_bitboard(to)) & ROOK_CORNERS != 0;
+        if is_king_move || touches_rook_corner {
+            self.zobrist_hash ^= self.white_castling_rights.zobrist_hash(true)
+                ^ self.black_castling_rights.zobrist_hash(false);
+            if is_king_move {
+                if white {
+                    self.white_castling_rights = CastlingRights::None;
+                } else {
+                    self.black_castling_rights = CastlingRights::None;
                 }
-                Piece::Bishop { .. } => {
-                    if new_board.white_to_move {
-                        new_board.black_bishops ^= captured_piece_bb;
-                        new_board.zobrist_hash ^= ZOBRISTS_BLACK_BISHOPS[to];
-                    } else {
-                        new_board.white_bishops ^= captured_piece_bb;
-                        new_board.zobrist_hash ^= ZOBRISTS_WHITE_BISHOPS[to];
+            }
+            for square in [from, to] {
+                match square {
+                    WHITE_KINGSIDE_CASTLE_ROOK_INDEX => {
+                        self.white_castling_rights = self
+                            .white_castling_rights
+                            .remove_side(CastlingRights::OnlyKingSide)
                     }
-                }
-                Piece::Rook { .. } => {
-                    if new_board.white_to_move {
-                        new_board.black_rooks ^= captured_piece_bb;
-                        new_board.zobrist_hash ^= ZOBRISTS_BLACK_ROOKS[to];
-                    } else {
-                        new_board.white_rooks ^= captured_piece_bb;
-                        new_board.zobrist_hash ^= ZOBRISTS_WHITE_ROOKS[to];
+                    WHITE_QUEENSIDE_CASTLE_ROOK_INDEX => {
+                        self.white_castling_rights = self
+                            .white_castling_rights
+                            .remove_side(CastlingRights::OnlyQueenSide)
                     }
-                }
-                Piece::Queen { .. } => {
-                    if new_board.white_to_move {
-                        new_board.black_queens ^= captured_piece_bb;
-                        new_board.zobrist_hash ^= ZOBRISTS_BLACK_QUEENS[to];
-                    } else {
-                        new_board.white_queens ^= captured_piece_bb;
-                        new_board.zobrist_hash ^= ZOBRISTS_WHITE_QUEENS[to];
+                    BLACK_KINGSIDE_CASTLE_ROOK_INDEX => {
+                        self.black_castling_rights = self
+                            .black_castling_rights
+                            .remove_side(CastlingRights::OnlyKingSide)
                     }
-                }
-                // King capture is illegal and should not happen
-                Piece::King { .. } => panic!("Cannot capture a king"),
-                Piece::None => {
-                    if !_move.is_en_passant() {
-                        panic!("Capture flag set, but no piece on target square.")
+                    BLACK_QUEENSIDE_CASTLE_ROOK_INDEX => {
+                        self.black_castling_rights = self
+                            .black_castling_rights
+                            .remove_side(CastlingRights::OnlyQueenSide)
                     }
+                    _ => {}
                 }
             }
+            self.zobrist_hash ^= self.white_castling_rights.zobrist_hash(true)
+                ^ self.black_castling_rights.zobrist_hash(false);
         }
 
-        new_board.pieces[to] = moved_piece;
-        new_board.pieces[from] = Piece::None;
-
-        // Update the bitboard of the moving piece
-
-        match moved_piece {
-            Piece::Pawn { .. } => {
-                if new_board.white_to_move {
-                    new_board.zobrist_hash ^= ZOBRISTS_WHITE_PAWNS[from]; // Remove from original position
-                    new_board.zobrist_hash ^= ZOBRISTS_WHITE_PAWNS[to]; // Add to new position
-                    new_board.white_pawns ^= move_mask;
-
-                    let was_double_push = to - from == 16;
-                    if was_double_push {
-                        // Polyglot spec: hash only if a pawn can actually perform the capture.
-                        let ep_square_index = to - 8;
-                        let adjacent_files_mask = WHITE_FREE_PAWN_ATTACKS_LOOKUP[ep_square_index];
-                        let can_capture_ep = (new_board.black_pawns & adjacent_files_mask) != 0;
+        let pawn_moved = matches!(moved_piece, Piece::Pawn { .. });
+        if pawn_moved && from.abs_diff(to) == 16 {
+            // Polyglot spec: hash only if a pawn can actually perform the capture.
+            let ep_square_index = (from + to) / 2;
+            let can_capture_ep = if white {
+                self.black_pawns & WHITE_FREE_PAWN_ATTACKS_LOOKUP[ep_square_index] != 0
+            } else {
+                self.white_pawns & BLACK_FREE_PAWN_ATTACKS_LOOKUP[ep_square_index] != 0
+            };
+            if can_capture_ep {
+                self.en_passant_target = square_index_to_bitboard(ep_square_index);
+                self.zobrist_hash ^= ZOBRISTS_EN_PASSANT_FILE[to % 8];
+            }
+        }
 
-                        if can_capture_ep {
-                            new_board.en_passant_target = square_index_to_bitboard(to - 8);
-                            let ep_file = (to % 8) as usize;
-                            new_board.zobrist_hash ^= ZOBRISTS_EN_PASSANT_FILE[ep_file];
-                        }
-                    }
-                } else {
-                    new_board.zobrist_hash ^= ZOBRISTS_BLACK_PAWNS[from]; // Remove from original position
-                    new_board.zobrist_hash ^= ZOBRISTS_BLACK_PAWNS[to]; // Add to new position
-                    new_board.black_pawns ^= move_mask;
+        self.update_board_state(pawn_moved, _move.is_capture());
+        undo_info
+    }
 
-                    let was_double_push = from - to == 16;
-                    if was_double_push {
-                        // Polyglot spec: hash only if a pawn can actually perform the capture.
-                        let ep_square_index = to + 8;
-                        let adjacent_files_mask = BLACK_FREE_PAWN_ATTACKS_LOOKUP[ep_square_index];
-                        let can_capture_ep = (new_board.white_pawns & adjacent_files_mask) != 0;
+    /// Takes back `_move`, which has to be the last move made with `make_move`.
+    pub fn unmake_move(&mut self, _move: Move, undo_info: UndoInfo) {
+        let from = _move.from();
+        let to = _move.to();
 
-                        if can_capture_ep {
-                            new_board.en_passant_target = square_index_to_bitboard(to + 8);
-                            let ep_file = (to % 8) as usize;
-                            new_board.zobrist_hash ^= ZOBRISTS_EN_PASSANT_FILE[ep_file];
-                        }
-                    }
-                }
-            }
-            Piece::Knight { .. } => {
-                if new_board.white_to_move {
-                    new_board.white_knights ^= move_mask;
-                    // Update Zobrist hash for moving piece
-                    new_board.zobrist_hash ^= ZOBRISTS_WHITE_KNIGHTS[from]; // Remove from original position
-                    new_board.zobrist_hash ^= ZOBRISTS_WHITE_KNIGHTS[to]; // Add to new position
-                } else {
-                    new_board.black_knights ^= move_mask;
-                    // Update Zobrist hash for moving piece
-                    new_board.zobrist_hash ^= ZOBRISTS_BLACK_KNIGHTS[from]; // Remove from original position
-                    new_board.zobrist_hash ^= ZOBRISTS_BLACK_KNIGHTS[to]; // Add to new position
-                }
-            }
-            Piece::Bishop { .. } => {
-                if new_board.white_to_move {
-                    new_board.white_bishops ^= move_mask;
-                    // Update Zobrist hash for moving piece
-                    new_board.zobrist_hash ^= ZOBRISTS_WHITE_BISHOPS[from]; // Remove from original position
-                    new_board.zobrist_hash ^= ZOBRISTS_WHITE_BISHOPS[to]; // Add to new position
-                } else {
-                    new_board.black_bishops ^= move_mask;
-                    // Update Zobrist hash for moving piece
-                    new_board.zobrist_hash ^= ZOBRISTS_BLACK_BISHOPS[from]; // Remove from original position
-                    new_board.zobrist_hash ^= ZOBRISTS_BLACK_BISHOPS[to]; // Add to new position
-                }
-            }
-            Piece::Rook { .. } => {
-                if new_board.white_to_move {
-                    new_board.white_rooks ^= move_mask;
-                    // Update Zobrist hash for moving piece
-                    new_board.zobrist_hash ^= ZOBRISTS_WHITE_ROOKS[from]; // Remove from original position
-                    new_board.zobrist_hash ^= ZOBRISTS_WHITE_ROOKS[to]; // Add to new position
-                } else {
-                    new_board.black_rooks ^= move_mask;
-                    // Update Zobrist hash for moving piece
-                    new_board.zobrist_hash ^= ZOBRISTS_BLACK_ROOKS[from]; // Remove from original position
-                    new_board.zobrist_hash ^= ZOBRISTS_BLACK_ROOKS[to]; // Add to new position
-                }
-            }
-            Piece::Queen { .. } => {
-                if new_board.white_to_move {
-                    new_board.white_queens ^= move_mask;
-                    // Update Zobrist hash for moving piece
-                    new_board.zobrist_hash ^= ZOBRISTS_WHITE_QUEENS[from]; // Remove from original position
-                    new_board.zobrist_hash ^= ZOBRISTS_WHITE_QUEENS[to]; // Add to new position
-                } else {
-                    new_board.black_queens ^= move_mask;
-                    // Update Zobrist hash for moving piece
-                    new_board.zobrist_hash ^= ZOBRISTS_BLACK_QUEENS[from]; // Remove from original position
-                    new_board.zobrist_hash ^= ZOBRISTS_BLACK_QUEENS[to]; // Add to new position
-                }
-            }
-            Piece::King { .. } => {
-                if new_board.white_to_move {
-                    new_board.white_king ^= move_mask;
-                    new_board.zobrist_hash ^= self.white_castling_rights.zobrist_hash(true);
-                    new_board.zobrist_hash ^= ZOBRISTS_WHITE_KINGS[from];
-                    new_board.zobrist_hash ^= ZOBRISTS_WHITE_KINGS[to];
-                    new_board.white_castling_rights = CastlingRights::None;
-                } else {
-                    new_board.black_king ^= move_mask;
-                    new_board.zobrist_hash ^= self.black_castling_rights.zobrist_hash(false);
-                    new_board.zobrist_hash ^= ZOBRISTS_BLACK_KINGS[from];
-                    new_board.zobrist_hash ^= ZOBRISTS_BLACK_KINGS[to];
-                    new_board.black_castling_rights = CastlingRights::None;
-                }
-            }
-            Piece::None => panic!(
-                "make_move tried to move a piece from an empty square: {}",
-                _move.to_uci()
-            ),
+        self.white_to_move = !self.white_to_move;
+        let white = self.white_to_move;
+        if !white {
+            self.full_move_number -= 1;
         }
 
-        // Update castling rights if a rook is moved or captured
-        if from == WHITE_KINGSIDE_CASTLE_ROOK_INDEX || to == WHITE_KINGSIDE_CASTLE_ROOK_INDEX {
-            new_board.white_castling_rights = new_board
-                .white_castling_rights
-                .remove_side(CastlingRights::OnlyKingSide);
-            new_board.zobrist_hash ^= self.white_castling_rights.zobrist_hash(true);
-            new_board.zobrist_hash ^= new_board.white_castling_rights.zobrist_hash(true);
+        let placed_piece = self.pieces[to];
+        self.toggle_piece(to, placed_piece);
+        self.pieces[to] = Piece::None;
+        let moved_piece = if _move.is_promotion() {
+            Piece::Pawn { white }
+        } else {
+            placed_piece
+        };
+        self.toggle_piece(from, moved_piece);
+        self.pieces[from] = moved_piece;
+
+        if _move.is_castle() {
+            let (rook_from, rook_to) = castling_rook_squares(&_move, white);
+            let rook = Piece::Rook { white };
+            self.toggle_piece(rook_to, rook);
+            self.pieces[rook_to] = Piece::None;
+            self.toggle_piece(rook_from, rook);
+            self.pieces[rook_from] = rook;
         }
-        if from == WHITE_QUEENSIDE_CASTLE_ROOK_INDEX || to == WHITE_QUEENSIDE_CASTLE_ROOK_INDEX {
-            new_board.white_castling_rights = new_board
-                .white_castling_rights
-                .remove_side(CastlingRights::OnlyQueenSide);
-            new_board.zobrist_hash ^= self.white_castling_rights.zobrist_hash(true);
-            new_board.zobrist_hash ^= new_board.white_castling_rights.zobrist_hash(true);
+
+        if undo_info.captured_piece != Piece::None {
+            let captured_square = captured_square(&_move, white);
+            self.toggle_piece(captured_square, undo_info.captured_piece);
+            self.pieces[captured_square] = undo_info.captured_piece;
         }
-        if from == BLACK_KINGSIDE_CASTLE_ROOK_INDEX || to == BLACK_KINGSIDE_CASTLE_ROOK_INDEX {
-            new_board.black_castling_rights = new_board
-                .black_castling_rights
-                .remove_side(CastlingRights::OnlyKingSide);
-            new_board.zobrist_hash ^= self.black_castling_rights.zobrist_hash(false);
-            new_board.zobrist_hash ^= new_board.black_castling_rights.zobrist_hash(false);
+
+        self.en_passant_target = undo_info.en_passant_target;
+        self.white_castling_rights = undo_info.white_castling_rights;
+        self.black_castling_rights = undo_info.black_castling_rights;
+        self.halfmove_clock = undo_info.halfmove_clock;
+        self.zobrist_hash = undo_info.zobrist_hash;
+        self.pawn_key = undo_info.pawn_key;
+        self.phase = undo_info.phase;
+    }
+
+    /// Passes the turn to the opponent, used for null move pruning.
+    /// Undo it with `unmake_null_move`.
+    pub fn make_null_move(&mut self) -> UndoInfo {
+        let undo_info = UndoInfo {
+            captured_piece: Piece::None,
+            en_passant_target: self.en_passant_target,
+            white_castling_rights: self.white_castling_rights,
+            black_castling_rights: self.black_castling_rights,
+            halfmove_clock: self.halfmove_clock,
+            zobrist_hash: self.zobrist_hash,
+            pawn_key: self.pawn_key,
+            phase: self.phase,
+        };
+        if self.en_passant_target != 0 {
+            let ep_file = bitboard_to_square_index(self.en_passant_target) % 8;
+            self.zobrist_hash ^= ZOBRISTS_EN_PASSANT_FILE[ep_file];
+            self.en_passant_target = 0;
         }
-        if from == BLACK_QUEENSIDE_CASTLE_ROOK_INDEX || to == BLACK_QUEENSIDE_CASTLE_ROOK_INDEX {
-            new_board.black_castling_rights = new_board
-                .black_castling_rights
-                .remove_side(CastlingRights::OnlyQueenSide);
-            new_board.zobrist_hash ^= self.black_castling_rights.zobrist_hash(false);
-            new_board.zobrist_hash ^= new_board.black_castling_rights.zobrist_hash(false);
+        self.update_board_state(false, false);
+        undo_info
+    }
+
+    pub fn unmake_null_move(&mut self, undo_info: UndoInfo) {
+        self.white_to_move = !self.white_to_move;
+        if !self.white_to_move {
+            self.full_move_number -= 1;
         }
+        self.en_passant_target = undo_info.en_passant_target;
+        self.halfmove_clock = undo_info.halfmove_clock;
+        self.zobrist_hash = undo_info.zobrist_hash;
+    }
 
-        // Handle promotions
-        if _move.is_promotion() {
-            if new_board.white_to_move {
-                new_board.white_pawns ^= to_bb;
-                new_board.zobrist_hash ^= ZOBRISTS_WHITE_PAWNS[to]
-            } else {
-                new_board.black_pawns ^= to_bb;
-                new_board.zobrist_hash ^= ZOBRISTS_BLACK_PAWNS[to]
-            }
-            let promoted_piece = match _move.promotion_piece() {
-                Some(PieceKind::Queen) => {
-                    if new_board.white_to_move {
-                        new_board.white_queens |= to_bb;
-                        new_board.zobrist_hash ^= ZOBRISTS_WHITE_QUEENS[to];
-                    } else {
-                        new_board.black_queens |= to_bb;
-                        new_board.zobrist_hash ^= ZOBRISTS_BLACK_QUEENS[to];
-                    }
-                    Piece::Queen {
-                        white: new_board.white_to_move,
-                    }
-                }
-                Some(PieceKind::Rook) => {
-                    if new_board.white_to_move {
-                        new_board.white_rooks |= to_bb;
-                        new_board.zobrist_hash ^= ZOBRISTS_WHITE_ROOKS[to];
-                    } else {
-                        new_board.black_rooks |= to_bb;
-                        new_board.zobrist_hash ^= ZOBRISTS_BLACK_ROOKS[to];
-                    }
-                    Piece::Rook {
-                        white: new_board.white_to_move,
-                    }
-                }
-                Some(PieceKind::Bishop) => {
-                    if new_board.white_to_move {
-                        new_board.white_bishops |= to_bb;
-                        new_board.zobrist_hash ^= ZOBRISTS_WHITE_BISHOPS[to];
-                    } else {
-                        new_board.black_bishops |= to_bb;
-                        new_board.zobrist_hash ^= ZOBRISTS_BLACK_BISHOPS[to];
-                    }
-                    Piece::Bishop {
-                        white: new_board.white_to_move,
-                    }
-                }
-                Some(PieceKind::Knight) => {
-                    if new_board.white_to_move {
-                        new_board.white_knights |= to_bb;
-                        new_board.zobrist_hash ^= ZOBRISTS_WHITE_KNIGHTS[to];
-                    } else {
-                        new_board.black_knights |= to_bb;
-                        new_board.zobrist_hash ^= ZOBRISTS_BLACK_KNIGHTS[to];
-                    }
-                    Piece::Knight {
-                        white: new_board.white_to_move,
-                    }
-                }
-                _ => panic!("Invalid promotion"),
-            };
-            new_board.pieces[to] = promoted_piece;
+    fn add_piece(&mut self, square: usize, piece: Piece) {
+        self.toggle_piece(square, piece);
+        self.pieces[square] = piece;
+        self.zobrist_hash ^= piece_zobrist(piece, square);
+        self.phase += phase_weight(piece);
+        if matches!(piece, Piece::Pawn { .. }) {
+            self.pawn_key ^= piece_zobrist(piece, square);
         }
+    }
 
-        if _move.is_en_passant() {
-            let ep_target_index = if new_board.white_to_move {
-                to - 8
-            } else {
-                to + 8
-            };
+    fn remove_piece(&mut self, square: usize, piece: Piece) {
+        self.toggle_piece(square, piece);
+        self.pieces[square] = Piece::None;
+        self.zobrist_hash ^= piece_zobrist(piece, square);
+        self.phase -= phase_weight(piece);
+        if matches!(piece, Piece::Pawn { .. }) {
+            self.pawn_key ^= piece_zobrist(piece, square);
+        }
+    }
 
-            new_board.pieces[ep_target_index] = Piece::None;
-            if new_board.white_to_move {
-                new_board.black_pawns ^= square_index_to_bitboard(ep_target_index);
-                new_board.zobrist_hash ^= ZOBRISTS_BLACK_PAWNS[ep_target_index];
-            } else {
-                new_board.white_pawns ^= square_index_to_bitboard(ep_target_index);
-                new_board.zobrist_hash ^= ZOBRISTS_WHITE_PAWNS[ep_target_index];
+    /// Flips `square` in the bit board of `piece` and in the combined bit boards.
+    #[inline(always)]
+    fn toggle_piece(&mut self, square: usize, piece: Piece) {
+        let bit_board = square_index_to_bitboard(square);
+        match piece {
+            Piece::Pawn { white: true } => {
+                self.white_pawns ^= bit_board;
+                self.white_pieces ^= bit_board;
+            }
+            Piece::Knight { white: true } => {
+                self.white_knights ^= bit_board;
+                self.white_pieces ^= bit_board;
+            }
+            Piece::Bishop { white: true } => {
+                self.white_bishops ^= bit_board;
+                self.white_pieces ^= bit_board;
             }
+            Piece::Rook { white: true } => {
+                self.white_rooks ^= bit_board;
+                self.white_pieces ^= bit_board;
+            }
+            Piece::Queen { white: true } => {
+                self.white_queens ^= bit_board;
+                self.white_pieces ^= bit_board;
+            }
+            Piece::King { white: true } => {
+                self.white_king ^= bit_board;
+                self.white_pieces ^= bit_board;
+            }
+            Piece::Pawn { white: false } => {
+                self.black_pawns ^= bit_board;
+                self.black_pieces ^= bit_board;
+            }
+            Piece::Knight { white: false } => {
+                self.black_knights ^= bit_board;
+                self.black_pieces ^= bit_board;
+            }
+            Piece::Bishop { white: false } => {
+                self.black_bishops ^= bit_board;
+                self.black_pieces ^= bit_board;
+            }
+            Piece::Rook { white: false } => {
+                self.black_rooks ^= bit_board;
+                self.black_pieces ^= bit_board;
+            }
+            Piece::Queen { white: false } => {
+                self.black_queens ^= bit_board;
+                self.black_pieces ^= bit_board;
+            }
+            Piece::King { white: false } => {
+                self.black_king ^= bit_board;
+                self.black_pieces ^= bit_board;
+            }
+            Piece::None => return,
         }
-
-        new_board.update_board_state(
-            moved_piece
-                == Piece::Pawn {
-                    white: new_board.white_to_move,
-                },
-            _move.is_capture(),
-        );
-        new_board.recompute_combined_bit_boards();
-        new_board
+        self.all_pieces ^= bit_board;
     }
+
     fn update_board_state(&mut self, pawn_moved: bool, was_capture: bool) {
         if pawn_moved || was_capture {
             self.halfmove_clock = 0;
@@ -1207,7 +1069,7 @@ impl Board {
                 new_hash ^= ZOBRISTS_BLACK_ROOKS[BLACK_KINGSIDE_CASTLE_ROOK_INDEX];
                 new_hash ^= ZOBRISTS_BLACK_ROOKS[BLACK_KINGSIDE_CASTLE_ROOK_INDEX - 2];
             }
-            return new_hash;
+            return new_hash ^ ZOBRISTS_WHITE_TO_MOVE;
         } else if _move.is_castle_long() {
             if self.white_to_move {
                 new_hash ^= self.white_castling_rights.zobrist_hash(true);
@@ -1222,7 +1084,7 @@ impl Board {
                 new_hash ^= ZOBRISTS_BLACK_ROOKS[BLACK_QUEENSIDE_CASTLE_ROOK_INDEX];
                 new_hash ^= ZOBRISTS_BLACK_ROOKS[BLACK_QUEENSIDE_CASTLE_ROOK_INDEX + 3];
             }
-            return new_hash;
+            return new_hash ^ ZOBRISTS_WHITE_TO_MOVE;
         }
 
         if _move.is_capture() {
@@ -1418,36 +1280,81 @@ impl Board {
             }
         }
 
-        if _move.is_en_passant() {
-            if self.white_to_move {
-                let ep_target_index = to - 8;
-                new_hash ^= ZOBRISTS_BLACK_PAWNS[ep_target_index];
-            } else {
-                let ep_target_index = to + 8;
-                new_hash ^= ZOBRISTS_WHITE_PAWNS[ep_target_index];
-            }
-        }
-
         new_hash ^= ZOBRISTS_WHITE_TO_MOVE;
         new_hash
     }
+}
 
-    fn recompute_combined_bit_boards(&mut self) {
-        self.white_pieces = self.white_pawns
-            | self.white_knights
-            | self.white_bishops
-            | self.white_rooks
-            | self.white_queens
-            | self.white_king;
-        self.black_pieces = self.black_pawns
-            | self.black_knights
-            | self.black_bishops
-            | self.black_rooks
-            | self.black_queens
-            | self.black_king;
-        self.all_pieces = self.white_pieces | self.black_pieces;
+const ROOK_CORNERS: u64 = (1 << WHITE_KINGSIDE_CASTLE_ROOK_INDEX)
+    | (1 << WHITE_QUEENSIDE_CASTLE_ROOK_INDEX)
+    | (1 << BLACK_KINGSIDE_CASTLE_ROOK_INDEX)
+    | (1 << BLACK_QUEENSIDE_CASTLE_ROOK_INDEX);
+
+/// The square of the piece taken by a capture, differs from `to` only for en passant.
+#[inline(always)]
+fn captured_square(_move: &Move, white_to_move: bool) -> usize {
+    if !_move.is_en_passant() {
+        _move.to()
+    } else if white_to_move {
+        _move.to() - 8
+    } else {
+        _move.to() + 8
     }
 }
+
+/// Start and end square of the rook for a castling move.
+#[inline(always)]
+fn castling_rook_squares(_move: &Move, white_to_move: bool) -> (usize, usize) {
+    match (_move.is_castle_short(), white_to_move) {
+        (true, true) => (
+            WHITE_KINGSIDE_CASTLE_ROOK_INDEX,
+            WHITE_KINGSIDE_CASTLE_ROOK_INDEX - 2,
+        ),
+        (false, true) => (
+            WHITE_QUEENSIDE_CASTLE_ROOK_INDEX,
+            WHITE_QUEENSIDE_CASTLE_ROOK_INDEX + 3,
+        ),
+        (true, false) => (
+            BLACK_KINGSIDE_CASTLE_ROOK_INDEX,
+            BLACK_KINGSIDE_CASTLE_ROOK_INDEX - 2,
+        ),
+        (false, false) => (
+            BLACK_QUEENSIDE_CASTLE_ROOK_INDEX,
+            BLACK_QUEENSIDE_CASTLE_ROOK_INDEX + 3,
+        ),
+    }
+}
+
+#[inline(always)]
+pub(crate) fn promoted_piece(piece_kind: PieceKind, white: bool) -> Piece {
+    match piece_kind {
+        PieceKind::Knight => Piece::Knight { white },
+        PieceKind::Bishop => Piece::Bishop { white },
+        PieceKind::Rook => Piece::Rook { white },
+        PieceKind::Queen => Piece::Queen { white },
+        _ => panic!("Invalid promotion"),
+    }
+}
+
+#[inline(always)]
+fn piece_zobrist(piece: Piece, square: usize) -> u64 {
+    match piece {
+        Piece::Pawn { white: true } => ZOBRISTS_WHITE_PAWNS[square],
+        Piece::Knight { white: true } => ZOBRISTS_WHITE_KNIGHTS[square],
+        Piece::Bishop { white: true } => ZOBRISTS_WHITE_BISHOPS[square],
+        Piece::Rook { white: true } => ZOBRISTS_WHITE_ROOKS[square],
+        Piece::Queen { white: true } => ZOBRISTS_WHITE_QUEENS[square],
+        Piece::King { white: true } => ZOBRISTS_WHITE_KINGS[square],
+        Piece::Pawn { white: false } => ZOBRISTS_BLACK_PAWNS[square],
+        Piece::Knight { white: false } => ZOBRISTS_BLACK_KNIGHTS[square],
+        Piece::Bishop { white: false } => ZOBRISTS_BLACK_BISHOPS[square],
+        Piece::Rook { white: false } => ZOBRISTS_BLACK_ROOKS[square],
+        Piece::Queen { white: false } => ZOBRISTS_BLACK_QUEENS[square],
+        Piece::King { white: false } => ZOBRISTS_BLACK_KINGS[square],
+        Piece::None => 0,
+    }
+}
+
 // src/move_gen.rs
 // ... (rest of the file is unchanged)
 
@@ -1522,7 +1429,7 @@ mod tests {
                 Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1")
                     .unwrap();
             for uci_move in *moves {
-                board = board.make_uci_move_temp(uci_move);
+                board = board.make_uci_move_temp(uci_move).0;
             }
             assert_eq!(
                 board.zobrist_hash, *expected_hash,
@@ -1560,6 +1467,7 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "rand")]
     #[test]
     fn test_zobrist_hash_for_random_moves_in_a_loop() {
         let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"; // Starting position
@@ -1576,10 +1484,10 @@ mod tests {
             }
 
             // Pick a random move from the list of legal moves.
-            let random_index = rand::random::<usize>() % moves.len();
+            let random_index = rand::random::<u32>() as usize % moves.len();
             let random_move = &moves[random_index];
 
-            let calculated_hash = board.calculate_new_zobrist_hash(random_move);
+            let calculated_hash = board.zobrist_after(random_move);
             let new_board = board.make_move_temp(random_move);
 
             assert_eq!(
@@ -1614,10 +1522,10 @@ mod tests {
         let initial_hash = board.zobrist_hash;
 
         // Sequence of moves that returns to the same position
-        board = board.make_uci_move_temp("b1a1");
-        board = board.make_uci_move_temp("b8a6");
-        board = board.make_uci_move_temp("a1b1");
-        board = board.make_uci_move_temp("a6b8");
+        board = board.make_uci_move_temp("b1a1").0;
+        board = board.make_uci_move_temp("b8a6").0;
+        board = board.make_uci_move_temp("a1b1").0;
+        board = board.make_uci_move_temp("a6b8").0;
 
         assert_eq!(
             initial_hash, board.zobrist_hash,
@@ -1632,10 +1540,10 @@ mod tests {
         let initial_hash = board.zobrist_hash;
 
         // Sequence of moves that returns to the same position
-        board = board.make_uci_move_temp("g1f3");
-        board = board.make_uci_move_temp("g8f6");
-        board = board.make_uci_move_temp("f3g1");
-        board = board.make_uci_move_temp("f6g8");
+        board = board.make_uci_move_temp("g1f3").0;
+        board = board.make_uci_move_temp("g8f6").0;
+        board = board.make_uci_move_temp("f3g1").0;
+        board = board.make_uci_move_temp("f6g8").0;
 
         assert_eq!(
             initial_hash, board.zobrist_hash,
@@ -1648,18 +1556,18 @@ mod tests {
         // Path 1: 1. e4 e5 2. Nf3 Nc6
         let mut board1 =
             Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
-        board1 = board1.make_uci_move_temp("e2e4");
-        board1 = board1.make_uci_move_temp("e7e5");
-        board1 = board1.make_uci_move_temp("g1f3");
-        board1 = board1.make_uci_move_temp("b8c6");
+        board1 = board1.make_uci_move_temp("e2e4").0;
+        board1 = board1.make_uci_move_temp("e7e5").0;
+        board1 = board1.make_uci_move_temp("g1f3").0;
+        board1 = board1.make_uci_move_temp("b8c6").0;
 
         // Path 2: 1. Nf3 Nc6 2. e4 e5
         let mut board2 =
             Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
-        board2 = board2.make_uci_move_temp("g1f3");
-        board2 = board2.make_uci_move_temp("b8c6");
-        board2 = board2.make_uci_move_temp("e2e4");
-        board2 = board2.make_uci_move_temp("e7e5");
+        board2 = board2.make_uci_move_temp("g1f3").0;
+        board2 = board2.make_uci_move_temp("b8c6").0;
+        board2 = board2.make_uci_move_temp("e2e4").0;
+        board2 = board2.make_uci_move_temp("e7e5").0;
 
         assert_eq!(
             board1.zobrist_hash, board2.zobrist_hash,
@@ -1671,27 +1579,27 @@ mod tests {
     fn test_zobrist_transposition_with_capture() {
         let mut path1 =
             Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
-        path1 = path1.make_uci_move_temp("f2f4");
-        path1 = path1.make_uci_move_temp("b8c6");
-        path1 = path1.make_uci_move_temp("f4f5");
-        path1 = path1.make_uci_move_temp("e7e5");
-        path1 = path1.make_uci_move_temp("f5e6");
-        path1 = path1.make_uci_move_temp("d7e6");
-        path1 = path1.make_uci_move_temp("e1f2");
+        path1 = path1.make_uci_move_temp("f2f4").0;
+        path1 = path1.make_uci_move_temp("b8c6").0;
+        path1 = path1.make_uci_move_temp("f4f5").0;
+        path1 = path1.make_uci_move_temp("e7e5").0;
+        path1 = path1.make_uci_move_temp("f5e6").0;
+        path1 = path1.make_uci_move_temp("d7e6").0;
+        path1 = path1.make_uci_move_temp("e1f2").0;
 
         let mut path2 =
             Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
-        path2 = path2.make_uci_move_temp("f2f4");
-        path2 = path2.make_uci_move_temp("b8c6");
-        path2 = path2.make_uci_move_temp("e1f2");
-        path2 = path2.make_uci_move_temp("e7e6");
-        path2 = path2.make_uci_move_temp("f4f5");
-        path2 = path2.make_uci_move_temp("d8g5");
-        path2 = path2.make_uci_move_temp("f5e6");
-        path2 = path2.make_uci_move_temp("d7e6");
-        path2 = path2.make_uci_move_temp("g1f3");
-        path2 = path2.make_uci_move_temp("g5d8");
-        path2 = path2.make_uci_move_temp("f3g1");
+        path2 = path2.make_uci_move_temp("f2f4").0;
+        path2 = path2.make_uci_move_temp("b8c6").0;
+        path2 = path2.make_uci_move_temp("e1f2").0;
+        path2 = path2.make_uci_move_temp("e7e6").0;
+        path2 = path2.make_uci_move_temp("f4f5").0;
+        path2 = path2.make_uci_move_temp("d8g5").0;
+        path2 = path2.make_uci_move_temp("f5e6").0;
+        path2 = path2.make_uci_move_temp("d7e6").0;
+        path2 = path2.make_uci_move_temp("g1f3").0;
+        path2 = path2.make_uci_move_temp("g5d8").0;
+        path2 = path2.make_uci_move_temp("f3g1").0;
 
         assert_eq!(
             path1.zobrist_hash, path2.zobrist_hash,
@@ -1699,18 +1607,39 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_make_and_unmake_null_move() {
+        let fen = "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3";
+        let mut board = Board::from_fen(fen).unwrap();
+        let before = board;
+
+        let undo_info = board.make_null_move();
+        assert!(!board.white_to_move);
+        assert_eq!(board.en_passant_target, 0);
+        // the same as the position with black to move and no en passant square
+        let passed =
+            Board::from_fen("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 3").unwrap();
+        assert_eq!(board.zobrist_hash, passed.zobrist_hash);
+
+        board.unmake_null_move(undo_info);
+        assert_eq!(board.zobrist_hash, before.zobrist_hash);
+        assert_eq!(board.en_passant_target, before.en_passant_target);
+        assert_eq!(board.white_to_move, before.white_to_move);
+        assert_eq!(board, before);
+    }
+
     #[test]
     fn test_zobrist_transposition_with_promotion() {
         // Path 1: Pawn on b7 captures rook on a8 and promotes to a Queen.
         let mut board1 =
             Board::from_fen("rnbqkbnr/pP1ppppp/8/8/8/8/PPPP1PPP/RNB1KBNR w KQkq - 0 1").unwrap();
-        board1 = board1.make_uci_move_temp("b7a8q");
+        board1 = board1.make_uci_move_temp("b7a8q").0;
 
         // Path 2: A different sequence of moves leading to the same position.
         // A queen on e4 moves to a8.
         let mut board2 =
             Board::from_fen("rnbqkbnr/p2ppppp/8/8/4Q3/8/PPPP1PPP/RNB1KBNR w KQkq - 0 1").unwrap();
-        board2 = board2.make_uci_move_temp("e4a8");
+        board2 = board2.make_uci_move_temp("e4a8").0;
 
         assert_eq!(
             board1.to_fen(),
@@ -0,0 +1,218 @@
+use crate::bit_boards::*;
+
+// (middlegame, endgame) scores for one pawn
+const DOUBLED_PAWN: (i16, i16) = (-10, -20);
+const ISOLATED_PAWN: (i16, i16) = (-10, -15);
+const BACKWARD_PAWN: (i16, i16) = (-8, -10);
+// A pawn defended by another pawn
+const PAWN_CHAIN: (i16, i16) = (8, 5);
+// Indexed by the rank of the pawn, seen from its own side
+const PASSED_PAWN: [(i16, i16); 8] = [
+    (0, 0),
+    (0, 5),
+    (5, 10),
+    (10, 20),
+    (20, 35),
+    (35, 60),
+    (60, 100),
+    (0, 0),
+];
+
+const PAWN_TABLE_SIZE: usize = 1 << 14;
+
+#[derive(Clone, Copy, Default)]
+struct PawnEntry {
+    pawn_key: u64,
+    middlegame: i16,
+    endgame: i16,
+}
+
+/// Caches the pawn structure evaluation by `Board::pawn_key`, the pawns change rarely
+/// compared to the other pieces. Every search thread has its own table.
+pub struct PawnTable {
+    entries: Vec<PawnEntry>,
+}
+
+impl Default for PawnTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PawnTable {
+    pub fn new() -> Self {
+        // An empty entry has key 0 and scores 0, which is right for a board without pawns.
+        PawnTable {
+            entries: vec![PawnEntry::default(); PAWN_TABLE_SIZE],
+        }
+    }
+
+    /// The (middlegame, endgame) pawn structure score from white's point of view.
+    pub fn probe_or_evaluate(
+        &mut self,
+        pawn_key: u64,
+        white_pawns: u64,
+        black_pawns: u64,
+    ) -> (i16, i16) {
+        let entry = &mut self.entries[pawn_key as usize % PAWN_TABLE_SIZE];
+        if entry.pawn_key != pawn_key {
+            let (middlegame, endgame) = pawn_structure(white_pawns, black_pawns);
+            *entry = PawnEntry {
+                pawn_key,
+                middlegame,
+                endgame,
+            };
+        }
+        (entry.middlegame, entry.endgame)
+    }
+}
+
+/// Doubled, isolated, backward, passed and defended pawns, as a (middlegame, endgame)
+/// score from white's point of view.
+pub fn pawn_structure(white_pawns: u64, black_pawns: u64) -> (i16, i16) {
+    let white = side_pawn_structure(white_pawns, black_pawns, true);
+    let black = side_pawn_structure(black_pawns, white_pawns, false);
+    (white.0 - black.0, white.1 - black.1)
+}
+
+/// The pawn structure score of one side, as a bonus for that side.
+pub fn side_pawn_structure(own_pawns: u64, enemy_pawns: u64, white: bool) -> (i16, i16) {
+    let mut score = (0, 0);
+    let mut add = |term: (i16, i16)| {
+        score.0 += term.0;
+        score.1 += term.1;
+    };
+
+    for file in FILES {
+        let pawns_on_file = (own_pawns & file).count_ones() as i16;
+        if pawns_on_file > 1 {
+            add((
+                DOUBLED_PAWN.0 * (pawns_on_file - 1),
+                DOUBLED_PAWN.1 * (pawns_on_file - 1),
+            ));
+        }
+    }
+
+    let (passed_masks, support_masks, defenders, stop_square_attackers) = if white {
+        (
+            &WHITE_PASSED_PAWN_LOOKUP,
+            &WHITE_PAWN_SUPPORT_LOOKUP,
+            &BLACK_FREE_PAWN_ATTACKS_LOOKUP,
+            &WHITE_FREE_PAWN_ATTACKS_LOOKUP,
+        )
+    } else {
+        (
+            &BLACK_PASSED_PAWN_LOOKUP,
+            &BLACK_PAWN_SUPPORT_LOOKUP,
+            &WHITE_FREE_PAWN_ATTACKS_LOOKUP,
+            &BLACK_FREE_PAWN_ATTACKS_LOOKUP,
+        )
+    };
+
+    let mut pawns = own_pawns;
+    while pawns != 0 {
+        let square = pop_lsb(&mut pawns);
+        let file = square % 8;
+        let (relative_rank, stop_square) = if white {
+            (square / 8, square + 8)
+        } else {
+            (7 - square / 8, square - 8)
+        };
+
+        if own_pawns & ADJACENT_FILES_LOOKUP[file] == 0 {
+            add(ISOLATED_PAWN);
+        } else if own_pawns & support_masks[square] == 0
+            && enemy_pawns & stop_square_attackers[stop_square] != 0
+        {
+            // No pawn can come to its help, and it can't advance safely either
+            add(BACKWARD_PAWN);
+        }
+
+        // A pawn behind another one of the same color is not passed, the front one is
+        if (enemy_pawns | (own_pawns & FILES[file])) & passed_masks[square] == 0 {
+            add(PASSED_PAWN[relative_rank]);
+        }
+
+        if own_pawns & defenders[square] != 0 {
+            add(PAWN_CHAIN);
+        }
+    }
+    score
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{Board, pawn_key};
+
+    fn side_structure(fen: &str, white: bool) -> (i16, i16) {
+        let board = Board::from_fen(fen).unwrap();
+        if white {
+            side_pawn_structure(board.white_pawns, board.black_pawns, true)
+        } else {
+            side_pawn_structure(board.black_pawns, board.white_pawns, false)
+        }
+    }
+
+    #[test]
+    fn test_pawn_structure_terms() {
+        // an isolated passed pawn on d5
+        assert_eq!(
+            side_structure("4k3/8/8/3P4/8/8/8/4K3 w - - 0 1", true),
+            (
+                ISOLATED_PAWN.0 + PASSED_PAWN[4].0,
+                ISOLATED_PAWN.1 + PASSED_PAWN[4].1
+            )
+        );
+        // doubled, isolated pawns on c2 and c3 against a pawn on d5: c3 is not passed
+        assert_eq!(
+            side_structure("4k3/8/8/3p4/8/2P5/2P5/4K3 w - - 0 1", true),
+            (
+                DOUBLED_PAWN.0 + 2 * ISOLATED_PAWN.0,
+                DOUBLED_PAWN.1 + 2 * ISOLATED_PAWN.1
+            )
+        );
+        // the black chain f7, e6, d5: e6 and d5 are defended, and all of them are passed
+        assert_eq!(
+            side_structure("4k3/5p2/4p3/3p4/8/8/8/4K3 b - - 0 1", false),
+            (
+                2 * PAWN_CHAIN.0 + PASSED_PAWN[1].0 + PASSED_PAWN[2].0 + PASSED_PAWN[3].0,
+                2 * PAWN_CHAIN.1 + PASSED_PAWN[1].1 + PASSED_PAWN[2].1 + PASSED_PAWN[3].1
+            )
+        );
+        // no pawn can come to help d3 and black's e5 pawn covers its stop square,
+        // but d3 still defends c4
+        assert_eq!(
+            side_structure("4k3/8/1p6/4p3/2P5/3P4/8/4K3 w - - 0 1", true),
+            (
+                BACKWARD_PAWN.0 + PAWN_CHAIN.0,
+                BACKWARD_PAWN.1 + PAWN_CHAIN.1
+            )
+        );
+    }
+
+    #[test]
+    fn test_pawn_key() {
+        let mut board = Board::default();
+        for uci in ["e2e4", "d7d5", "e4d5", "g8f6", "g1f3"] {
+            let _move = board
+                .generate_legal_moves_temp()
+                .iter()
+                .copied()
+                .find(|m| m.to_uci() == uci)
+                .unwrap();
+            let pawn_key_before = board.pawn_key;
+            let undo_info = board.make_move(_move);
+            assert_eq!(
+                board.pawn_key,
+                pawn_key(board.white_pawns, board.black_pawns)
+            );
+            if !uci.starts_with('g') {
+                assert_ne!(board.pawn_key, pawn_key_before);
+            }
+            board.unmake_move(_move, undo_info);
+            assert_eq!(board.pawn_key, pawn_key_before);
+            board.make_move(_move);
+        }
+    }
+}
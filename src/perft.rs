@@ -0,0 +1,293 @@
+use crate::board::Board;
+use crate::moves::Move;
+
+/// A position with its known leaf node counts, `nodes[0]` is depth 1.
+pub struct PerftPosition {
+    pub name: &'static str,
+    pub fen: &'static str,
+    pub nodes: &'static [u64],
+}
+
+/// The standard perft positions from the chessprogramming wiki.
+pub const PERFT_SUITE: [PerftPosition; 6] = [
+    PerftPosition {
+        name: "Starting position",
+        fen: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        nodes: &[20, 400, 8_902, 197_281, 4_865_609, 119_060_324],
+    },
+    PerftPosition {
+        name: "Kiwipete",
+        fen: "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        nodes: &[48, 2_039, 97_862, 4_085_603, 193_690_690],
+    },
+    PerftPosition {
+        name: "Position 3",
+        fen: "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        nodes: &[14, 191, 2_812, 43_238, 674_624, 11_030_083, 178_633_661],
+    },
+    PerftPosition {
+        name: "Position 4",
+        fen: "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        nodes: &[6, 264, 9_467, 422_333, 15_833_292],
+    },
+    PerftPosition {
+        name: "Position 5",
+        fen: "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        nodes: &[44, 1_486, 62_379, 2_103_487, 89_941_194],
+    },
+    PerftPosition {
+        name: "Position 6",
+        fen: "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+        nodes: &[46, 2_079, 89_890, 3_894_594, 164_075_551],
+    },
+];
+
+/// Counts the leaf nodes of the legal move tree, every leaf is made with `make_move`.
+pub fn perft(board: &mut Board, depth: u8) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let mut nodes = 0;
+    for &_move in board.generate_legal_moves_temp().iter() {
+        let undo_info = board.make_move(_move);
+        nodes += perft(board, depth - 1);
+        board.unmake_move(_move, undo_info);
+    }
+    nodes
+}
+
+/// Like `perft`, but the last ply is counted with `generate_legal_moves_temp().len()`
+/// instead of making every leaf move.
+pub fn perft_bulk(board: &mut Board, depth: u8) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let moves = board.generate_legal_moves_temp();
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+    let mut nodes = 0;
+    for &_move in moves.iter() {
+        let undo_info = board.make_move(_move);
+        nodes += perft_bulk(board, depth - 1);
+        board.unmake_move(_move, undo_info);
+    }
+    nodes
+}
+
+/// Bulk counting perft that reuses the counts of transpositions from `table`.
+pub fn perft_hashed(board: &mut Board, depth: u8, table: &mut PerftTable) -> u64 {
+    if depth <= 1 {
+        return perft_bulk(board, depth);
+    }
+    if let Some(nodes) = table.probe(board.zobrist_hash, depth) {
+        return nodes;
+    }
+    let mut nodes = 0;
+    for &_move in board.generate_legal_moves_temp().iter() {
+        let undo_info = board.make_move(_move);
+        nodes += perft_hashed(board, depth - 1, table);
+        board.unmake_move(_move, undo_info);
+    }
+    table.insert(board.zobrist_hash, depth, nodes);
+    nodes
+}
+
+/// Leaf node counts for every root move, sorted by the UCI notation of the move.
+/// `count` is called with the position after each root move and the remaining depth,
+/// e.g. `perft` or `perft_bulk`.
+pub fn divide<F: FnMut(&mut Board, u8) -> u64>(
+    board: &mut Board,
+    depth: u8,
+    mut count: F,
+) -> Vec<(Move, u64)> {
+    let mut counts = Vec::new();
+    for _move in board.generate_legal_moves_temp() {
+        let undo_info = board.make_move(_move);
+        counts.push((_move, count(board, depth.saturating_sub(1))));
+        board.unmake_move(_move, undo_info);
+    }
+    counts.sort_by_key(|(_move, _)| _move.to_uci());
+    counts
+}
+
+// [Bits 0-7] depth, [Bits 8-63] node count
+const PERFT_DEPTH_MASK: u64 = 0xFF;
+const PERFT_NODES_SHIFT: u64 = 8;
+
+#[derive(Debug, Clone, Copy, Default)]
+struct PerftEntry {
+    zobrist_hash: u64,
+    data: u64,
+}
+
+/// Always-replace hash table for perft counts, keyed by zobrist hash and depth.
+pub struct PerftTable {
+    entries: Vec<PerftEntry>,
+}
+
+impl PerftTable {
+    pub fn with_size_mb(size_mb: usize) -> Self {
+        let num_entries = (size_mb * 1024 * 1024 / size_of::<PerftEntry>()).max(1);
+        Self {
+            entries: vec![PerftEntry::default(); num_entries],
+        }
+    }
+
+    fn index(&self, zobrist_hash: u64, depth: u8) -> usize {
+        // spread the depths of the same position over different slots
+        let key = zobrist_hash ^ (depth as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        (key % self.entries.len() as u64) as usize
+    }
+
+    pub fn probe(&self, zobrist_hash: u64, depth: u8) -> Option<u64> {
+        let entry = &self.entries[self.index(zobrist_hash, depth)];
+        if entry.zobrist_hash == zobrist_hash && entry.data & PERFT_DEPTH_MASK == depth as u64 {
+            Some(entry.data >> PERFT_NODES_SHIFT)
+        } else {
+            None
+        }
+    }
+
+    pub fn insert(&mut self, zobrist_hash: u64, depth: u8, nodes: u64) {
+        let index = self.index(zobrist_hash, depth);
+        self.entries[index] = PerftEntry {
+            zobrist_hash,
+            data: (nodes << PERFT_NODES_SHIFT) | depth as u64,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Keeps debug test runs fast, the perft binary checks the deeper counts.
+    const MAX_TEST_NODES: u64 = 200_000;
+
+    fn check_position(position: &PerftPosition) {
+        let mut board = Board::from_fen(position.fen).unwrap();
+        for (depth, expected) in position.nodes.iter().enumerate() {
+            if *expected > MAX_TEST_NODES {
+                break;
+            }
+            assert_eq!(
+                perft(&mut board, depth as u8 + 1),
+                *expected,
+                "{} at depth {}",
+                position.name,
+                depth + 1
+            );
+        }
+    }
+
+    #[test]
+    fn test_perft_starting_position() {
+        check_position(&PERFT_SUITE[0]);
+    }
+
+    #[test]
+    fn test_perft_kiwipete() {
+        check_position(&PERFT_SUITE[1]);
+    }
+
+    #[test]
+    fn test_perft_position_3() {
+        check_position(&PERFT_SUITE[2]);
+    }
+
+    #[test]
+    fn test_perft_position_4() {
+        check_position(&PERFT_SUITE[3]);
+    }
+
+    #[test]
+    fn test_perft_position_5() {
+        check_position(&PERFT_SUITE[4]);
+    }
+
+    #[test]
+    fn test_perft_position_6() {
+        check_position(&PERFT_SUITE[5]);
+    }
+
+    #[test]
+    fn test_en_passant_captures_checking_pawn() {
+        // c7c5+ was just played, bxc6 is the only capture of the checker
+        let board = Board::from_fen("8/8/8/1Pp5/1K6/8/8/7k w - c6 0 1").unwrap();
+        let moves = board.generate_legal_moves_temp();
+        assert!(
+            moves
+                .iter()
+                .any(|m| m.is_en_passant() && m.to_uci() == "b5c6")
+        );
+    }
+
+    #[test]
+    fn test_divide_sums_to_perft() {
+        let mut board = Board::from_fen(PERFT_SUITE[1].fen).unwrap();
+        let counts = divide(&mut board, 2, perft);
+        assert_eq!(counts.len(), 48);
+        assert_eq!(counts.iter().map(|(_, n)| n).sum::<u64>(), 2_039);
+    }
+
+    #[test]
+    fn test_bulk_and_hashed_match_perft() {
+        let mut table = PerftTable::with_size_mb(1);
+        for position in &PERFT_SUITE {
+            let mut board = Board::from_fen(position.fen).unwrap();
+            for depth in 0..=3 {
+                let expected = perft(&mut board, depth);
+                assert_eq!(perft_bulk(&mut board, depth), expected, "{}", position.name);
+                assert_eq!(
+                    perft_hashed(&mut board, depth, &mut table),
+                    expected,
+                    "{}",
+                    position.name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_hashed_perft_deeper() {
+        // enough transpositions at depth 5 to exercise probing
+        let mut table = PerftTable::with_size_mb(4);
+        let mut board = Board::from_fen(PERFT_SUITE[2].fen).unwrap();
+        assert_eq!(
+            perft_hashed(&mut board, 5, &mut table),
+            PERFT_SUITE[2].nodes[4]
+        );
+    }
+
+    /// Walks the move tree with `make_move` and checks that the incremental zobrist hash
+    /// matches a fresh one and that `unmake_move` restores the board exactly.
+    fn check_make_unmake(board: &mut Board, depth: u8) {
+        if depth == 0 {
+            return;
+        }
+        for _move in board.generate_legal_moves_temp() {
+            let before = *board;
+            let undo_info = board.make_move(_move);
+            let fen = board.to_fen();
+            assert_eq!(
+                board.zobrist_hash,
+                Board::from_fen(&fen).unwrap().zobrist_hash,
+                "hash after {} in {}",
+                _move.to_uci(),
+                before.to_fen()
+            );
+            check_make_unmake(board, depth - 1);
+            board.unmake_move(_move, undo_info);
+            assert_eq!(*board, before, "unmake {} in {}", _move.to_uci(), fen);
+        }
+    }
+
+    #[test]
+    fn test_make_unmake_restores_board() {
+        for position in &PERFT_SUITE {
+            let mut board = Board::from_fen(position.fen).unwrap();
+            check_make_unmake(&mut board, 2);
+        }
+    }
+}
@@ -0,0 +1,704 @@
+use crate::board::{Board, DEFAULT_FEN, FenError};
+use crate::moves::Move;
+use crate::san::SanError;
+use std::fmt;
+use std::iter::Peekable;
+use std::vec::IntoIter;
+
+/// The tags every PGN export has to start with, in this order.
+const SEVEN_TAG_ROSTER: [&str; 7] = ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+const MAX_LINE_LENGTH: usize = 80;
+
+#[derive(Debug, Clone)]
+pub enum PgnError {
+    InvalidFen(FenError),
+    InvalidMove { error: SanError, fen: String },
+    InvalidTag(String),
+    UnbalancedVariation,
+    UnterminatedComment,
+    NoGame,
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PgnError::InvalidFen(e) => write!(f, "Invalid FEN tag: {}", e),
+            PgnError::InvalidMove { error, fen } => write!(f, "{} in position {}", error, fen),
+            PgnError::InvalidTag(s) => write!(f, "Invalid tag pair: {}", s),
+            PgnError::UnbalancedVariation => write!(f, "Unbalanced parentheses in movetext"),
+            PgnError::UnterminatedComment => write!(f, "Comment is never closed"),
+            PgnError::NoGame => write!(f, "No game found"),
+        }
+    }
+}
+
+impl std::error::Error for PgnError {}
+
+impl From<FenError> for PgnError {
+    fn from(e: FenError) -> Self {
+        PgnError::InvalidFen(e)
+    }
+}
+
+/// One move in the game tree together with the position it leads to.
+#[derive(Debug, Clone)]
+pub struct PgnNode {
+    pub _move: Move,
+    /// The position after `_move` was made.
+    pub board: Board,
+    pub parent: Option<usize>,
+    /// The first child continues the line, all others are variations.
+    pub children: Vec<usize>,
+    pub nags: Vec<u8>,
+    pub comment_before: Option<String>,
+    pub comment: Option<String>,
+}
+
+/// A single game: tag pairs and a tree of moves, starting at `start_board`.
+/// Nodes are stored in an arena and referenced by index, `None` stands for the start position.
+#[derive(Debug, Clone)]
+pub struct PgnGame {
+    pub tags: Vec<(String, String)>,
+    pub start_board: Board,
+    pub starting_comment: Option<String>,
+    nodes: Vec<PgnNode>,
+    root_children: Vec<usize>,
+}
+
+impl Default for PgnGame {
+    fn default() -> Self {
+        Self::new(Board::default())
+    }
+}
+
+impl PgnGame {
+    /// Creates an empty game with the seven tag roster filled with unknown values.
+    pub fn new(start_board: Board) -> Self {
+        let mut game = Self {
+            tags: SEVEN_TAG_ROSTER
+                .iter()
+                .map(|tag| {
+                    let value = match *tag {
+                        "Date" => "????.??.??",
+                        "Result" => "*",
+                        _ => "?",
+                    };
+                    (tag.to_string(), value.to_string())
+                })
+                .collect(),
+            start_board,
+            starting_comment: None,
+            nodes: Vec::new(),
+            root_children: Vec::new(),
+        };
+        if start_board.to_fen() != DEFAULT_FEN {
+            game.set_tag("SetUp", "1");
+            game.set_tag("FEN", &start_board.to_fen());
+        }
+        game
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(n, _)| n == name) {
+            Some(tag) => tag.1 = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    pub fn result(&self) -> &str {
+        self.tag("Result").unwrap_or("*")
+    }
+
+    pub fn set_result(&mut self, result: &str) {
+        self.set_tag("Result", result);
+    }
+
+    pub fn node(&self, index: usize) -> &PgnNode {
+        &self.nodes[index]
+    }
+
+    pub fn node_mut(&mut self, index: usize) -> &mut PgnNode {
+        &mut self.nodes[index]
+    }
+
+    /// The position after `node`, or the start position for `None`.
+    pub fn board_at(&self, node: Option<usize>) -> &Board {
+        match node {
+            Some(index) => &self.nodes[index].board,
+            None => &self.start_board,
+        }
+    }
+
+    pub fn children(&self, node: Option<usize>) -> &[usize] {
+        match node {
+            Some(index) => &self.nodes[index].children,
+            None => &self.root_children,
+        }
+    }
+
+    /// Adds `_move` after `parent` and returns the index of the new node.
+    /// If the move already exists there, the existing node is returned instead.
+    pub fn add_move(&mut self, parent: Option<usize>, _move: Move) -> usize {
+        if let Some(&existing) = self
+            .children(parent)
+            .iter()
+            .find(|&&child| self.nodes[child]._move == _move)
+        {
+            return existing;
+        }
+        let board = self.board_at(parent).make_move_temp(&_move);
+        let index = self.nodes.len();
+        self.nodes.push(PgnNode {
+            _move,
+            board,
+            parent,
+            children: Vec::new(),
+            nags: Vec::new(),
+            comment_before: None,
+            comment: None,
+        });
+        match parent {
+            Some(parent) => self.nodes[parent].children.push(index),
+            None => self.root_children.push(index),
+        }
+        index
+    }
+
+    /// Appends `_move` to the end of the main line, this is what bots use to record their games.
+    pub fn push_move(&mut self, _move: Move) -> usize {
+        let last = self.mainline_nodes().last().copied();
+        self.add_move(last, _move)
+    }
+
+    pub fn mainline_nodes(&self) -> Vec<usize> {
+        let mut line = Vec::new();
+        let mut current = self.root_children.first().copied();
+        while let Some(index) = current {
+            line.push(index);
+            current = self.nodes[index].children.first().copied();
+        }
+        line
+    }
+
+    pub fn mainline_moves(&self) -> Vec<Move> {
+        self.mainline_nodes()
+            .iter()
+            .map(|&index| self.nodes[index]._move)
+            .collect()
+    }
+
+    /// The position at the end of the main line.
+    pub fn final_board(&self) -> &Board {
+        self.board_at(self.mainline_nodes().last().copied())
+    }
+
+    /// Parses the first game of a PGN text.
+    pub fn parse(pgn: &str) -> Result<Self, PgnError> {
+        Self::parse_all(pgn)
+            .into_iter()
+            .next()
+            .unwrap_or(Err(PgnError::NoGame))
+    }
+
+    /// Parses every game of a PGN text, e.g. a file written by cutechess.
+    /// A broken game does not stop the parsing, the games after it are still returned.
+    pub fn parse_all(pgn: &str) -> Vec<Result<Self, PgnError>> {
+        let mut games = Vec::new();
+        let mut tokens = tokenize(pgn).into_iter().peekable();
+        while tokens.peek().is_some() {
+            games.push(Self::parse_game(&mut tokens));
+        }
+        games
+    }
+
+    /// Reads the tokens of one game, up to its result or the tags of the next game,
+    /// even if it fails on an earlier one. The first error is returned.
+    fn parse_game(
+        tokens: &mut Peekable<IntoIter<Result<Token, PgnError>>>,
+    ) -> Result<Self, PgnError> {
+        let mut error = None;
+        let mut tags = Vec::new();
+        while let Some(token) = tokens.next_if(is_tag) {
+            match token {
+                Ok(Token::Tag(name, value)) => tags.push((name, value)),
+                Ok(_) => unreachable!("only tags are taken"),
+                Err(e) => {
+                    error.get_or_insert(e);
+                }
+            }
+        }
+
+        let start_board = match tags.iter().find(|(name, _)| name == "FEN") {
+            Some((_, fen)) => Board::from_fen(fen).unwrap_or_else(|e| {
+                error.get_or_insert(e.into());
+                Board::default()
+            }),
+            None => Board::default(),
+        };
+        let mut game = Self::new(start_board);
+        for (name, value) in &tags {
+            game.set_tag(name, value);
+        }
+
+        let mut line = Line::default();
+        let mut has_result = false;
+        while let Some(token) = tokens.next_if(|token| !is_tag(token)) {
+            has_result = matches!(token, Ok(Token::Result(_)));
+            if let Err(e) = token.and_then(|token| game.read_movetext(token, &mut line)) {
+                error.get_or_insert(e);
+            }
+            if has_result {
+                break;
+            }
+        }
+        if !has_result && tokens.peek().is_some() {
+            error.get_or_insert(PgnError::InvalidTag("tag pair inside movetext".to_string()));
+        }
+        if !line.variation_stack.is_empty() {
+            error.get_or_insert(PgnError::UnbalancedVariation);
+        }
+        match error {
+            Some(error) => Err(error),
+            None => Ok(game),
+        }
+    }
+
+    /// Adds one movetext token to the game, `line` is where it is added.
+    fn read_movetext(&mut self, token: Token, line: &mut Line) -> Result<(), PgnError> {
+        match token {
+            Token::Tag(..) => {
+                return Err(PgnError::InvalidTag("tag pair inside movetext".to_string()));
+            }
+            Token::Comment(text) => match line.current {
+                Some(index) if line.pending_comment.is_none() => {
+                    append_comment(&mut self.nodes[index].comment, &text)
+                }
+                None if line.variation_stack.is_empty() && self.nodes.is_empty() => {
+                    append_comment(&mut self.starting_comment, &text)
+                }
+                _ => append_comment(&mut line.pending_comment, &text),
+            },
+            Token::Nag(nag) => {
+                if let Some(index) = line.current {
+                    self.nodes[index].nags.push(nag);
+                }
+            }
+            Token::VariationStart => {
+                // a variation replaces the last move, so it starts from that move's parent
+                let Some(index) = line.current else {
+                    return Err(PgnError::UnbalancedVariation);
+                };
+                line.variation_stack.push(line.current);
+                line.current = self.nodes[index].parent;
+            }
+            Token::VariationEnd => {
+                line.current = line
+                    .variation_stack
+                    .pop()
+                    .ok_or(PgnError::UnbalancedVariation)?;
+            }
+            Token::Result(result) => {
+                if !line.variation_stack.is_empty() {
+                    return Err(PgnError::UnbalancedVariation);
+                }
+                self.set_result(&result);
+            }
+            Token::San(san) => {
+                let board = self.board_at(line.current);
+                let _move = board
+                    .parse_san(&san)
+                    .map_err(|error| PgnError::InvalidMove {
+                        error,
+                        fen: board.to_fen(),
+                    })?;
+                // "!" and "?" suffixes are stored as their NAG equivalents
+                let nag = suffix_nag(&san);
+                let index = self.add_move(line.current, _move);
+                if let Some(nag) = nag {
+                    self.nodes[index].nags.push(nag);
+                }
+                if let Some(comment) = line.pending_comment.take() {
+                    self.nodes[index].comment_before = Some(comment);
+                }
+                line.current = Some(index);
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the game in export format: seven tag roster first, movetext wrapped at 80 characters.
+    pub fn to_pgn(&self) -> String {
+        let mut pgn = String::new();
+        for tag in SEVEN_TAG_ROSTER {
+            pgn.push_str(&format!(
+                "[{} \"{}\"]\n",
+                tag,
+                escape(self.tag(tag).unwrap_or("?"))
+            ));
+        }
+        for (name, value) in &self.tags {
+            if !SEVEN_TAG_ROSTER.contains(&name.as_str()) {
+                pgn.push_str(&format!("[{} \"{}\"]\n", name, escape(value)));
+            }
+        }
+        pgn.push('\n');
+
+        let mut tokens = Vec::new();
+        if let Some(comment) = &self.starting_comment {
+            tokens.push(format!("{{{}}}", comment));
+        }
+        self.write_line(None, &mut tokens, true);
+        tokens.push(self.result().to_string());
+
+        let mut line_length = 0;
+        for token in tokens {
+            if line_length > 0 && line_length + 1 + token.len() > MAX_LINE_LENGTH {
+                pgn.push('\n');
+                line_length = 0;
+            } else if line_length > 0 {
+                pgn.push(' ');
+                line_length += 1;
+            }
+            line_length += token.len();
+            pgn.push_str(&token);
+        }
+        pgn.push_str("\n\n");
+        pgn
+    }
+
+    fn write_line(&self, from: Option<usize>, tokens: &mut Vec<String>, mut force_number: bool) {
+        let mut current = from;
+        loop {
+            let children = self.children(current);
+            let Some(&main) = children.first() else {
+                return;
+            };
+            let board = self.board_at(current);
+
+            self.write_node(board, main, tokens, force_number);
+            force_number = false;
+
+            // variations to the main move come right after it
+            for &variation in &children[1..] {
+                // parentheses stick to the first and last token of the variation
+                let start = tokens.len();
+                self.write_node(board, variation, tokens, true);
+                self.write_line(Some(variation), tokens, true);
+                tokens[start].insert(0, '(');
+                tokens.last_mut().unwrap().push(')');
+                force_number = true;
+            }
+            current = Some(main);
+        }
+    }
+
+    fn write_node(
+        &self,
+        board: &Board,
+        index: usize,
+        tokens: &mut Vec<String>,
+        force_number: bool,
+    ) {
+        let node = &self.nodes[index];
+        if let Some(comment) = &node.comment_before {
+            tokens.push(format!("{{{}}}", comment));
+        }
+        if board.white_to_move {
+            tokens.push(format!("{}.", board.full_move_number));
+        } else if force_number || node.comment_before.is_some() {
+            tokens.push(format!("{}...", board.full_move_number));
+        }
+        tokens.push(board.move_to_san(&node._move));
+        for nag in &node.nags {
+            tokens.push(format!("${}", nag));
+        }
+        if let Some(comment) = &node.comment {
+            tokens.push(format!("{{{}}}", comment));
+        }
+    }
+}
+
+impl fmt::Display for PgnGame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_pgn())
+    }
+}
+
+/// Where the movetext is read: the current node of the line we are in, and a stack of lines
+/// to return to after variations.
+#[derive(Default)]
+struct Line {
+    current: Option<usize>,
+    variation_stack: Vec<Option<usize>>,
+    pending_comment: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Tag(String, String),
+    Comment(String),
+    Nag(u8),
+    VariationStart,
+    VariationEnd,
+    Result(String),
+    San(String),
+}
+
+/// Splits the text into tokens. An invalid tag is returned as an error in its place,
+/// so only the game it belongs to fails.
+fn tokenize(pgn: &str) -> Vec<Result<Token, PgnError>> {
+    let mut tokens = Vec::new();
+    let chars: Vec<char> = pgn.chars().collect();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            _ if c.is_whitespace() => i += 1,
+            '[' => {
+                let Some(end) = chars[i..].iter().position(|&c| c == ']').map(|p| p + i) else {
+                    tokens.push(Err(PgnError::InvalidTag(chars[i..].iter().collect())));
+                    break;
+                };
+                let inner: String = chars[i + 1..end].iter().collect();
+                let tag = inner
+                    .trim()
+                    .split_once(char::is_whitespace)
+                    .and_then(|(name, value)| {
+                        let value = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+                        Some(Token::Tag(name.to_string(), unescape(value)))
+                    })
+                    .ok_or(PgnError::InvalidTag(inner));
+                tokens.push(tag);
+                i = end + 1;
+            }
+            '{' => {
+                let Some(end) = chars[i..].iter().position(|&c| c == '}').map(|p| p + i) else {
+                    tokens.push(Err(PgnError::UnterminatedComment));
+                    break;
+                };
+                let text: String = chars[i + 1..end].iter().collect();
+                tokens.push(Ok(Token::Comment(
+                    text.split_whitespace().collect::<Vec<_>>().join(" "),
+                )));
+                i = end + 1;
+            }
+            ';' => {
+                // rest of line comment
+                let end = chars[i..]
+                    .iter()
+                    .position(|&c| c == '\n')
+                    .map(|p| p + i)
+                    .unwrap_or(chars.len());
+                let text: String = chars[i + 1..end].iter().collect();
+                tokens.push(Ok(Token::Comment(text.trim().to_string())));
+                i = end;
+            }
+            '%' if i == 0 || chars[i - 1] == '\n' => {
+                // escape mechanism, the whole line is ignored
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+            '(' => {
+                tokens.push(Ok(Token::VariationStart));
+                i += 1;
+            }
+            ')' => {
+                tokens.push(Ok(Token::VariationEnd));
+                i += 1;
+            }
+            _ => {
+                let start = i;
+                while i < chars.len()
+                    && !chars[i].is_whitespace()
+                    && !matches!(chars[i], '{' | '}' | '(' | ')' | '[' | ']' | ';')
+                {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                push_word_tokens(&word, &mut tokens);
+            }
+        }
+    }
+    tokens
+}
+
+/// Splits a whitespace free word like "12.Nf3", "12...e5" or "$14" into tokens.
+fn push_word_tokens(word: &str, tokens: &mut Vec<Result<Token, PgnError>>) {
+    if matches!(word, "1-0" | "0-1" | "1/2-1/2" | "*") {
+        tokens.push(Ok(Token::Result(word.to_string())));
+        return;
+    }
+    if let Some(nag) = word.strip_prefix('$') {
+        if let Ok(nag) = nag.parse() {
+            tokens.push(Ok(Token::Nag(nag)));
+        }
+        return;
+    }
+    // castling written with zeros would otherwise be mistaken for a move number
+    let san = if word.starts_with("0-0") {
+        word
+    } else {
+        word.trim_start_matches(|c: char| c.is_ascii_digit())
+            .trim_start_matches('.')
+    };
+    if !san.is_empty() {
+        tokens.push(Ok(Token::San(san.to_string())));
+    }
+}
+
+fn is_tag(token: &Result<Token, PgnError>) -> bool {
+    matches!(token, Ok(Token::Tag(..)) | Err(PgnError::InvalidTag(_)))
+}
+
+fn append_comment(target: &mut Option<String>, text: &str) {
+    match target {
+        Some(existing) => {
+            existing.push(' ');
+            existing.push_str(text);
+        }
+        None => *target = Some(text.to_string()),
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn unescape(value: &str) -> String {
+    value.replace("\\\"", "\"").replace("\\\\", "\\")
+}
+
+fn suffix_nag(san: &str) -> Option<u8> {
+    let suffix = san.trim_start_matches(|c: char| c != '!' && c != '?');
+    match suffix {
+        "!" => Some(1),
+        "?" => Some(2),
+        "!!" => Some(3),
+        "??" => Some(4),
+        "!?" => Some(5),
+        "?!" => Some(6),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CUTECHESS_GAME: &str = r#"[Event "?"]
+[Site "?"]
+[Date "2025.07.12"]
+[Round "1"]
+[White "hhz-conditional_tt-10"]
+[Black "hhz-un_conditional_tt-10.5"]
+[Result "0-1"]
+[PlyCount "54"]
+
+1. f4 {book} d5 {book} 2. Nf3 {book} Nf6 {book} 3. b3 {book} g6 {book}
+4. Bb2 {book} Bg7 {book} 5. e3 {book} O-O {book} 6. Be2 {book} c5 {book}
+7. O-O {book} Nc6 {book} 8. Ne5 Qa5 {1.4s} 9. Nxc6 {1.3s} bxc6 10. Bc3 {0.51s}
+Qb6 11. a4 {0.84s} a5 {1.6s} 12. Qe1 {1.6s} d4 {1.5s} 13. Bb2 {3.7s} Bf5 {2.2s}
+14. Na3 {11s} Qb4 {5.4s} 15. Bf3 {15s} Rfc8 {23s} 16. e4 {21s} Be6 {11s}
+17. f5 {9.5s} gxf5 {28s} 18. exf5 {46s} Bxf5 {11s} 19. Bxc6 {1.6s} Rxc6 {1.3s}
+20. Rxf5 {0.81s} Re6 {1.0s} 21. Re5 Rxe5 22. Qxe5 Qxd2 23. Qxc5 Qe3+ 24. Kh1 Ne4
+25. Qd5 Nf2+ 26. Kg1 Nd1+ 27. Kf1 Qf2# {Black mates} 0-1
+"#;
+
+    #[test]
+    fn test_parse_cutechess_game() {
+        let game = PgnGame::parse(CUTECHESS_GAME).unwrap();
+        assert_eq!(game.tag("White"), Some("hhz-conditional_tt-10"));
+        assert_eq!(game.result(), "0-1");
+        assert_eq!(game.mainline_moves().len(), 54);
+        let last = *game.mainline_nodes().last().unwrap();
+        assert_eq!(game.node(last).comment.as_deref(), Some("Black mates"));
+        assert!(game.final_board().in_check_temp());
+        assert!(game.final_board().generate_legal_moves_temp().is_empty());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let game = PgnGame::parse(CUTECHESS_GAME).unwrap();
+        let written = game.to_pgn();
+        let reparsed = PgnGame::parse(&written).unwrap();
+        assert_eq!(game.mainline_moves(), reparsed.mainline_moves());
+        assert_eq!(written, reparsed.to_pgn());
+        assert!(written.lines().all(|line| line.len() <= MAX_LINE_LENGTH));
+    }
+
+    #[test]
+    fn test_variations_and_nags() {
+        let pgn = "1. e4 e5 (1... c5 $1 2. Nf3 (2. c3) d6) 2. Nf3?! {main} Nc6 *";
+        let game = PgnGame::parse(pgn).unwrap();
+
+        let first = game.children(None)[0];
+        let replies = game.children(Some(first));
+        assert_eq!(replies.len(), 2);
+        let sicilian = replies[1];
+        assert_eq!(game.node(sicilian).nags, vec![1]);
+        assert_eq!(game.children(Some(sicilian)).len(), 2);
+
+        let main_nf3 = game.mainline_nodes()[2];
+        assert_eq!(game.node(main_nf3).nags, vec![6]);
+        assert_eq!(game.node(main_nf3).comment.as_deref(), Some("main"));
+
+        let written = game.to_pgn();
+        assert!(
+            written.contains("1. e4 e5 (1... c5 $1 2. Nf3 (2. c3) 2... d6) 2. Nf3 $6 {main} Nc6 *")
+        );
+    }
+
+    #[test]
+    fn test_broken_games_do_not_stop_parsing() {
+        let pgn = r#"[Event "first"]
+
+1. e4 e5 1-0
+
+[Event "illegal move"]
+
+1. e4 e4 2. Nf3 (2. d4 0-1
+
+[Event "second"]
+
+1. d4 d5 *
+
+[Event no quotes]
+[Site "?"]
+
+1. c4 c5 1/2-1/2
+
+1. Nf3 Nf6 *
+"#;
+        let games = PgnGame::parse_all(pgn);
+        assert_eq!(games.len(), 5);
+        assert_eq!(games[0].as_ref().unwrap().tag("Event"), Some("first"));
+        assert!(matches!(games[1], Err(PgnError::InvalidMove { .. })));
+        let second = games[2].as_ref().unwrap();
+        assert_eq!(second.tag("Event"), Some("second"));
+        assert_eq!(second.mainline_moves().len(), 2);
+        assert!(matches!(games[3], Err(PgnError::InvalidTag(_))));
+        assert_eq!(games[4].as_ref().unwrap().mainline_moves().len(), 2);
+    }
+
+    #[test]
+    fn test_push_moves_from_fen() {
+        let board = Board::from_fen("8/8/8/8/8/5k2/8/4K2R w K - 0 1").unwrap();
+        let mut game = PgnGame::new(board);
+        let castle = board.parse_san("O-O").unwrap();
+        game.push_move(castle);
+        game.set_result("*");
+        let written = game.to_pgn();
+        assert!(written.contains("[FEN \"8/8/8/8/8/5k2/8/4K2R w K - 0 1\"]"));
+        assert!(written.contains("1. O-O+ *"));
+    }
+}
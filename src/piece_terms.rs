@@ -0,0 +1,176 @@
+use crate::bit_boards::*;
+use crate::board::{Board, CastlingRights};
+use crate::eval::EvalParams;
+
+// Squares by index, a1 = 0
+const A7: u64 = 1 << 48;
+const B6: u64 = 1 << 41;
+const H7: u64 = 1 << 55;
+const G6: u64 = 1 << 46;
+const KINGSIDE_ROOK_CORNER: u64 = (1 << 6) | (1 << 7);
+const KINGSIDE_KING_SQUARES: u64 = (1 << 5) | (1 << 6);
+const QUEENSIDE_ROOK_CORNER: u64 = 1 | (1 << 1);
+const QUEENSIDE_KING_SQUARES: u64 = (1 << 1) | (1 << 2);
+
+/// The pieces of one side, and the enemy pawns and king, as seen from white's side of the board.
+/// Black's pieces are mirrored, so every term only has to be written for white.
+struct SideView {
+    pawns: u64,
+    knights: u64,
+    bishops: u64,
+    rooks: u64,
+    king: u64,
+    enemy_pawns: u64,
+    enemy_king: u64,
+    can_castle: bool,
+}
+
+impl SideView {
+    fn of(board: &Board, white: bool) -> Self {
+        if white {
+            SideView {
+                pawns: board.white_pawns,
+                knights: board.white_knights,
+                bishops: board.white_bishops,
+                rooks: board.white_rooks,
+                king: board.white_king,
+                enemy_pawns: board.black_pawns,
+                enemy_king: board.black_king,
+                can_castle: board.white_castling_rights != CastlingRights::None,
+            }
+        } else {
+            SideView {
+                pawns: board.black_pawns.swap_bytes(),
+                knights: board.black_knights.swap_bytes(),
+                bishops: board.black_bishops.swap_bytes(),
+                rooks: board.black_rooks.swap_bytes(),
+                king: board.black_king.swap_bytes(),
+                enemy_pawns: board.white_pawns.swap_bytes(),
+                enemy_king: board.white_king.swap_bytes(),
+                can_castle: board.black_castling_rights != CastlingRights::None,
+            }
+        }
+    }
+}
+
+/// Bishop pair, rooks on open files and the seventh rank, knight outposts and trapped pieces,
+/// as a (middlegame, endgame) score from white's point of view.
+pub fn piece_terms(board: &Board, params: &EvalParams) -> (i16, i16) {
+    let white = side_piece_terms(board, true, params);
+    let black = side_piece_terms(board, false, params);
+    (white.0 - black.0, white.1 - black.1)
+}
+
+/// The piece terms of one side, as a bonus for that side.
+pub fn side_piece_terms(board: &Board, white: bool, params: &EvalParams) -> (i16, i16) {
+    side_view_terms(&SideView::of(board, white), params)
+}
+
+fn side_view_terms(side: &SideView, params: &EvalParams) -> (i16, i16) {
+    let mut score = (0, 0);
+    let mut add = |weight: (i16, i16), count: u32| {
+        score.0 += weight.0 * count as i16;
+        score.1 += weight.1 * count as i16;
+    };
+
+    if side.bishops.count_ones() >= 2 {
+        add(params.bishop_pair, 1);
+    }
+
+    let mut rooks = side.rooks;
+    while rooks != 0 {
+        let file = FILES[pop_lsb(&mut rooks) % 8];
+        if (side.pawns | side.enemy_pawns) & file == 0 {
+            add(params.rook_open_file, 1);
+        } else if side.pawns & file == 0 {
+            add(params.rook_semi_open_file, 1);
+        }
+    }
+    // Only worth it if there are pawns to eat there, or the enemy king is cut off behind it
+    if side.enemy_pawns & RANK_7 != 0 || side.enemy_king & RANK_8 != 0 {
+        add(params.rook_on_seventh, (side.rooks & RANK_7).count_ones());
+    }
+
+    let mut knights = side.knights & (RANK_4 | RANK_5 | RANK_6);
+    while knights != 0 {
+        let square = pop_lsb(&mut knights);
+        let defended = side.pawns & BLACK_FREE_PAWN_ATTACKS_LOOKUP[square] != 0;
+        let can_be_chased =
+            side.enemy_pawns & WHITE_PASSED_PAWN_LOOKUP[square] & ADJACENT_FILES_LOOKUP[square % 8]
+                != 0;
+        if defended && !can_be_chased {
+            add(params.knight_outpost, 1);
+        }
+    }
+
+    // A bishop that took the pawn on a7 or h7 and gets locked in by b6 or g6
+    let trapped_bishops = (side.bishops & A7 != 0 && side.enemy_pawns & B6 != 0) as u32
+        + (side.bishops & H7 != 0 && side.enemy_pawns & G6 != 0) as u32;
+    add(params.trapped_bishop, trapped_bishops);
+
+    // A rook in the corner behind a king that can't castle anymore
+    if !side.can_castle {
+        let trapped_kingside =
+            side.king & KINGSIDE_KING_SQUARES != 0 && side.rooks & KINGSIDE_ROOK_CORNER > side.king;
+        let trapped_queenside = side.king & QUEENSIDE_KING_SQUARES != 0
+            && side.rooks & QUEENSIDE_ROOK_CORNER != 0
+            && side.rooks & QUEENSIDE_ROOK_CORNER < side.king;
+        add(
+            params.trapped_rook,
+            trapped_kingside as u32 + trapped_queenside as u32,
+        );
+    }
+    score
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn terms(fen: &str) -> (i16, i16) {
+        piece_terms(&Board::from_fen(fen).unwrap(), &EvalParams::DEFAULT)
+    }
+
+    #[test]
+    fn test_piece_terms() {
+        let params = EvalParams::DEFAULT;
+        assert_eq!(
+            terms("4k3/8/8/8/8/8/8/2B1KB2 w - - 0 1"),
+            params.bishop_pair
+        );
+        // a rook on an open file, for black
+        let (middlegame, endgame) = params.rook_open_file;
+        assert_eq!(
+            terms("3rk3/8/8/8/8/8/8/4K3 w - - 0 1"),
+            (-middlegame, -endgame)
+        );
+        // a semi-open file with an enemy pawn on it, and that pawn is on the seventh rank
+        assert_eq!(
+            terms("4k3/3p4/8/8/8/8/8/3RK3 w - - 0 1"),
+            params.rook_semi_open_file
+        );
+        assert_eq!(
+            terms("4k3/2pR4/8/8/8/8/8/4K3 w - - 0 1"),
+            (
+                params.rook_open_file.0 + params.rook_on_seventh.0,
+                params.rook_open_file.1 + params.rook_on_seventh.1
+            )
+        );
+        // a knight on d5 defended by e4, no black pawn can chase it away
+        assert_eq!(
+            terms("4k3/p7/8/3N4/4P3/8/8/4K3 w - - 0 1"),
+            params.knight_outpost
+        );
+        assert_eq!(terms("4k3/2p5/8/3N4/4P3/8/8/4K3 w - - 0 1"), (0, 0));
+        assert_eq!(
+            terms("4k3/B7/1p6/8/8/8/8/4K3 w - - 0 1"),
+            params.trapped_bishop
+        );
+        // the king walked to f1 instead of castling
+        assert_eq!(
+            terms("4k3/8/8/8/8/8/5PPP/5K1R w - - 0 1"),
+            params.trapped_rook
+        );
+        assert_eq!(terms("4k3/8/8/8/8/8/5PPP/4K2R w K - 0 1"), (0, 0));
+    }
+}
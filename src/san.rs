@@ -0,0 +1,338 @@
+use crate::bit_boards::square_index_to_square;
+use crate::board::{Board, Piece, PieceKind};
+use crate::moves::{Move, square_to_algebraic};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SanError {
+    InvalidSyntax(String),
+    IllegalMove(String),
+    AmbiguousMove(String),
+}
+
+impl fmt::Display for SanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SanError::InvalidSyntax(san) => write!(f, "Invalid SAN: {}", san),
+            SanError::IllegalMove(san) => write!(f, "No legal move matches {}", san),
+            SanError::AmbiguousMove(san) => write!(f, "More than one legal move matches {}", san),
+        }
+    }
+}
+
+impl std::error::Error for SanError {}
+
+impl Board {
+    /// Formats a legal move in standard algebraic notation, e.g. "Nbd7", "exd6", "e8=Q+" or "O-O#".
+    pub fn move_to_san(&self, _move: &Move) -> String {
+        let mut san = if _move.is_castle_short() {
+            "O-O".to_string()
+        } else if _move.is_castle_long() {
+            "O-O-O".to_string()
+        } else {
+            let piece_kind = piece_kind_of(self.pieces[_move.from()]).unwrap_or(PieceKind::Pawn);
+            let from_square = square_index_to_square(_move.from());
+            let mut san = String::new();
+
+            if piece_kind == PieceKind::Pawn {
+                if _move.is_capture() {
+                    san.push((b'a' + from_square.file as u8) as char);
+                }
+            } else {
+                san.push(piece_kind_char(piece_kind));
+
+                // other pieces of the same kind that could go to the same square
+                let ambiguous: Vec<Move> = self
+                    .generate_legal_moves_temp()
+                    .into_iter()
+                    .filter(|m| {
+                        m.to() == _move.to()
+                            && m.from() != _move.from()
+                            && piece_kind_of(self.pieces[m.from()]) == Some(piece_kind)
+                    })
+                    .collect();
+                if !ambiguous.is_empty() {
+                    let same_file = ambiguous.iter().any(|m| m.from() % 8 == _move.from() % 8);
+                    let same_rank = ambiguous.iter().any(|m| m.from() / 8 == _move.from() / 8);
+                    if !same_file {
+                        san.push((b'a' + from_square.file as u8) as char);
+                    } else if !same_rank {
+                        san.push((b'1' + from_square.rank as u8) as char);
+                    } else {
+                        san.push_str(&square_to_algebraic(from_square));
+                    }
+                }
+            }
+            if _move.is_capture() {
+                san.push('x');
+            }
+            san.push_str(&square_to_algebraic(square_index_to_square(_move.to())));
+            if let Some(promotion) = _move.promotion_piece() {
+                san.push('=');
+                san.push(piece_kind_char(promotion));
+            }
+            san
+        };
+
+        let after = self.make_move_temp(_move);
+        if after.in_check_temp() {
+            if after.generate_legal_moves_temp().is_empty() {
+                san.push('#');
+            } else {
+                san.push('+');
+            }
+        }
+        san
+    }
+
+    /// Finds the legal move written in standard algebraic notation.
+    /// Besides strict SAN this accepts "0-0", promotions without "=" ("e8Q"), missing or extra
+    /// capture markers, "-" between squares, "e.p." and trailing check or annotation symbols.
+    pub fn parse_san(&self, san: &str) -> Result<Move, SanError> {
+        let original = san;
+        let san = san.trim();
+        let san = san.strip_suffix("e.p.").unwrap_or(san).trim_end();
+        let san = san.trim_end_matches(['+', '#', '!', '?']);
+        let legal_moves = self.generate_legal_moves_temp();
+
+        let castle = match san {
+            "O-O" | "0-0" | "o-o" => Some(true),
+            "O-O-O" | "0-0-0" | "o-o-o" => Some(false),
+            _ => None,
+        };
+        if let Some(is_short) = castle {
+            return legal_moves
+                .iter()
+                .find(|m| {
+                    if is_short {
+                        m.is_castle_short()
+                    } else {
+                        m.is_castle_long()
+                    }
+                })
+                .copied()
+                .ok_or_else(|| SanError::IllegalMove(original.to_string()));
+        }
+
+        let invalid = || SanError::InvalidSyntax(original.to_string());
+
+        let (san, promotion) = match san.split_once('=') {
+            Some((rest, piece)) => {
+                let mut chars = piece.chars();
+                let promotion = chars
+                    .next()
+                    .map(|c| c.to_ascii_uppercase())
+                    .and_then(piece_kind_from_char)
+                    .ok_or_else(invalid)?;
+                if chars.next().is_some() {
+                    return Err(invalid());
+                }
+                (rest, Some(promotion))
+            }
+            None => match san.chars().last().and_then(piece_kind_from_char) {
+                // "e8Q", a rank followed by a piece letter can only be a promotion
+                Some(piece_kind) if san[..san.len() - 1].ends_with(['1', '8']) => {
+                    (&san[..san.len() - 1], Some(piece_kind))
+                }
+                _ => (san, None),
+            },
+        };
+
+        let piece_kind = san
+            .chars()
+            .next()
+            .and_then(piece_kind_from_char)
+            .unwrap_or(PieceKind::Pawn);
+        let body = if piece_kind == PieceKind::Pawn {
+            san
+        } else {
+            &san[1..]
+        };
+        let body: String = body
+            .chars()
+            .filter(|c| !matches!(c, 'x' | ':' | '-'))
+            .collect();
+        if body.len() < 2 || !body.is_ascii() {
+            return Err(invalid());
+        }
+        let (disambiguation, destination) = body.split_at(body.len() - 2);
+        let to = square_from_str(destination).ok_or_else(invalid)?;
+        if disambiguation.len() > 2
+            || !disambiguation
+                .chars()
+                .all(|c| matches!(c, 'a'..='h' | '1'..='8'))
+        {
+            return Err(invalid());
+        }
+
+        let mut candidates = legal_moves.iter().filter(|m| {
+            m.to() == to
+                && !m.is_castle()
+                && piece_kind_of(self.pieces[m.from()]) == Some(piece_kind)
+                // a promotion without a piece matches all four, which is reported as ambiguous
+                && (m.promotion_piece() == promotion || promotion.is_none())
+                && disambiguation.chars().all(|c| match c {
+                    'a'..='h' => m.from() % 8 == (c as u8 - b'a') as usize,
+                    _ => m.from() / 8 == (c as u8 - b'1') as usize,
+                })
+        });
+
+        match (candidates.next(), candidates.next()) {
+            (Some(_move), None) => Ok(*_move),
+            (Some(_), Some(_)) => Err(SanError::AmbiguousMove(original.to_string())),
+            (None, _) => Err(SanError::IllegalMove(original.to_string())),
+        }
+    }
+}
+
+fn piece_kind_from_char(c: char) -> Option<PieceKind> {
+    match c {
+        'N' => Some(PieceKind::Knight),
+        'B' => Some(PieceKind::Bishop),
+        'R' => Some(PieceKind::Rook),
+        'Q' => Some(PieceKind::Queen),
+        'K' => Some(PieceKind::King),
+        _ => None,
+    }
+}
+
+fn piece_kind_of(piece: Piece) -> Option<PieceKind> {
+    match piece {
+        Piece::None => None,
+        Piece::Pawn { .. } => Some(PieceKind::Pawn),
+        Piece::Knight { .. } => Some(PieceKind::Knight),
+        Piece::Bishop { .. } => Some(PieceKind::Bishop),
+        Piece::Rook { .. } => Some(PieceKind::Rook),
+        Piece::Queen { .. } => Some(PieceKind::Queen),
+        Piece::King { .. } => Some(PieceKind::King),
+    }
+}
+
+fn piece_kind_char(piece_kind: PieceKind) -> char {
+    match piece_kind {
+        PieceKind::Pawn => 'P',
+        PieceKind::Knight => 'N',
+        PieceKind::Bishop => 'B',
+        PieceKind::Rook => 'R',
+        PieceKind::Queen => 'Q',
+        PieceKind::King => 'K',
+    }
+}
+
+fn square_from_str(square: &str) -> Option<usize> {
+    let mut chars = square.chars();
+    let file = chars.next()?;
+    let rank = chars.next()?;
+    if !('a'..='h').contains(&file) || !('1'..='8').contains(&rank) {
+        return None;
+    }
+    Some((rank as u8 - b'1') as usize * 8 + (file as u8 - b'a') as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_san_round_trip() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b KQkq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        ];
+        for fen in fens {
+            let board = Board::from_fen(fen).unwrap();
+            for m in board.generate_legal_moves_temp() {
+                let san = board.move_to_san(&m);
+                assert_eq!(board.parse_san(&san), Ok(m), "{} in {}", san, fen);
+            }
+        }
+    }
+
+    #[test]
+    fn test_disambiguation() {
+        // rooks on a1 and h1 can both reach d1
+        let board = Board::from_fen("3k4/8/8/8/8/8/4K3/R6R w - - 0 1").unwrap();
+        let m = board.parse_san("Rad1").unwrap();
+        assert_eq!(board.move_to_san(&m), "Rad1+");
+        assert_eq!(
+            board.parse_san("Rd1"),
+            Err(SanError::AmbiguousMove("Rd1".to_string()))
+        );
+
+        // knights on b1 and b5 share the file
+        let board = Board::from_fen("4k3/8/8/1N6/8/8/8/1N2K3 w - - 0 1").unwrap();
+        let m = board.parse_san("N1c3").unwrap();
+        assert_eq!(board.move_to_san(&m), "N1c3");
+
+        // queens on a1, a3 and c1 all reach b2, a1 needs the full square
+        let board = Board::from_fen("4k3/8/8/8/8/Q7/8/Q1Q1K3 w - - 0 1").unwrap();
+        let m = board.parse_san("Qa1b2").unwrap();
+        assert_eq!(board.move_to_san(&m), "Qa1b2");
+    }
+
+    #[test]
+    fn test_check_and_mate_suffixes() {
+        let board = Board::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        let m = board.parse_san("Ra8").unwrap();
+        assert_eq!(board.move_to_san(&m), "Ra8#");
+
+        // third position of assets/ssm_4_5_men.epd
+        let board = Board::from_fen("1k2q3/8/4N3/8/2Q5/3K4/8/8 w - - 0 1").unwrap();
+        let m = board.parse_san("Qc7+").unwrap();
+        assert_eq!(m.to_uci(), "c4c7");
+        assert_eq!(board.move_to_san(&m), "Qc7+");
+    }
+
+    #[test]
+    fn test_tolerated_variants() {
+        let board = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        assert!(board.parse_san("0-0").unwrap().is_castle_short());
+        assert!(board.parse_san("O-O-O+").unwrap().is_castle_long());
+
+        let board = Board::from_fen("3q1k2/4P3/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let promotion = board.parse_san("e8=Q").unwrap();
+        assert_eq!(board.parse_san("e8Q"), Ok(promotion));
+        assert_eq!(board.parse_san("exd8=N").unwrap().to_uci(), "e7d8n");
+        assert_eq!(board.parse_san("ed8N"), board.parse_san("exd8=N"));
+        assert_eq!(
+            board.parse_san("e8"),
+            Err(SanError::AmbiguousMove("e8".to_string()))
+        );
+
+        let board = Board::default();
+        assert_eq!(board.parse_san("Ng1-f3").unwrap().to_uci(), "g1f3");
+        assert_eq!(board.parse_san("Nxf3").unwrap().to_uci(), "g1f3");
+
+        let board = Board::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+        assert_eq!(board.parse_san("exd6 e.p.").unwrap().to_uci(), "e5d6");
+    }
+
+    #[test]
+    fn test_errors() {
+        let board = Board::default();
+        assert!(matches!(
+            board.parse_san("e5"),
+            Err(SanError::IllegalMove(_))
+        ));
+        assert!(matches!(
+            board.parse_san("O-O"),
+            Err(SanError::IllegalMove(_))
+        ));
+        assert!(matches!(
+            board.parse_san("Zf3"),
+            Err(SanError::InvalidSyntax(_))
+        ));
+        assert!(matches!(
+            board.parse_san(""),
+            Err(SanError::InvalidSyntax(_))
+        ));
+        assert!(matches!(
+            board.parse_san("e9"),
+            Err(SanError::InvalidSyntax(_))
+        ));
+    }
+}
@@ -1,30 +1,414 @@
 use crate::board::Board;
-use crate::eval::{eval, pieces_score};
+use crate::eval::{EvalParams, eval, pieces_score};
 use crate::metrics::{SearchMetrics, TimingKind};
-use crate::moves::{Move, MoveList};
+use crate::moves::{MAX_NUM_MOVES, Move, MoveList};
+use crate::pawn_structure::PawnTable;
 use crate::tt_table::{NodeType, TT_Table};
+use arrayvec::ArrayVec;
 use std::option::Option;
 use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Instant;
 
 const MIN_SCORE: i16 = i16::MIN + 2;
 const MAX_SCORE: i16 = i16::MAX - 1;
 
+// A mate found `ply` plies from the root scores `WHITE_WINS - ply` or `BLACK_WINS + ply`,
+// so a faster mate is preferred and a slower one when getting mated.
 const WHITE_WINS: i16 = MAX_SCORE - 1;
 const BLACK_WINS: i16 = MIN_SCORE + 1;
+// Scores this close to a win are mates, no evaluation gets near them.
+const MATE_THRESHOLD: i16 = WHITE_WINS - MAX_PLY as i16;
 
 const SEARCH_CANCELED: i16 = i16::MIN;
 
+// Looking at the clock is not free, so the deadline is only checked every this many nodes
+const TIME_CHECK_INTERVAL: u64 = 2048;
+
+// The null move search is reduced by this many plies on top of the usual one
+const NULL_MOVE_REDUCTION: u8 = 2;
+
+// Half width of the first aspiration window around the score of the previous iteration
+const ASPIRATION_WINDOW: i16 = 50;
+// Once a side of the window is widened this far, it is opened completely. Otherwise a fail-hard
+// search can keep returning the bound of a window that never reaches a mate score.
+const ASPIRATION_FULL_WINDOW: i16 = 1000;
+
+// Late move reductions: after this many moves quiet moves are searched one ply shallower,
+// if there are at least this many plies left
+const LMR_FULL_DEPTH_MOVES: usize = 3;
+const LMR_MIN_DEPTH: u8 = 3;
+const LMR_REDUCTION: u8 = 1;
+
+// How much a quiet move may gain at most, by remaining depth. Used for futility pruning.
+const FUTILITY_MARGINS: [i16; 3] = [0, 200, 400];
+// Reverse futility pruning cuts nodes up to this depth, with this margin per ply
+const REVERSE_FUTILITY_MAX_DEPTH: u8 = 3;
+const REVERSE_FUTILITY_MARGIN: i16 = 120;
+
+/// Switches for the selective search techniques, so they can be compared against each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchConfig {
+    pub late_move_reductions: bool,
+    pub futility_pruning: bool,
+    pub reverse_futility_pruning: bool,
+}
+
+impl SearchConfig {
+    pub const DEFAULT: SearchConfig = SearchConfig {
+        late_move_reductions: true,
+        futility_pruning: true,
+        reverse_futility_pruning: true,
+    };
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// How a move that does not give check is searched, decided by its parent node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum QuietMove {
+    FullDepth,
+    Reduced,
+    Futile,
+}
+
+/// How `search_move` searches a move, decided by its parent node.
+#[derive(Debug, Clone, Copy)]
+struct MoveFlags {
+    is_first_move: bool,
+    quiet_move: QuietMove,
+}
+
+/// A node of the search tree: the plies left to search, its distance from the root,
+/// its window and the number of moves that reset the halfmove clock on the way to it.
+#[derive(Debug, Clone, Copy)]
+struct Node {
+    depth: u8,
+    ply: usize,
+    alpha: i16,
+    beta: i16,
+    num_resetting_moves: u8,
+}
+
+impl Node {
+    fn with_window(self, alpha: i16, beta: i16) -> Self {
+        Node {
+            alpha,
+            beta,
+            ..self
+        }
+    }
+}
+
+pub const MAX_PLY: usize = 128;
+const KILLER_SLOTS: usize = 2;
+
+/// Search state that is kept across the iterations of iterative deepening: move ordering
+/// heuristics, the principal variation and statistics for reporting.
+pub struct SearchContext {
+    /// Quiet moves that caused a beta cutoff, per ply, the most recent one first.
+    killers: [[Move; KILLER_SLOTS]; MAX_PLY],
+    /// How much quiet moves caused beta cutoffs, indexed by from and to square.
+    history: [[u32; 64]; 64],
+    /// Triangular PV table: row `ply` holds the best line found from `ply` on,
+    /// in the entries `ply..pv_length[ply]`.
+    pv_table: [[Move; MAX_PLY]; MAX_PLY],
+    pv_length: [usize; MAX_PLY],
+    /// Only written by the thread searching with this context, shared so the nodes of
+    /// helper threads can be reported while they search.
+    nodes: Arc<AtomicU64>,
+    seldepth: usize,
+    pub config: SearchConfig,
+    /// The search is stopped when this moment passes.
+    pub deadline: Option<Instant>,
+    /// Cleared to stop the search. Threads that search together share it, so they stop together.
+    pub should_search: Arc<AtomicBool>,
+    pub eval_params: EvalParams,
+    pawn_table: PawnTable,
+}
+
+impl SearchContext {
+    pub fn new() -> Self {
+        Self {
+            killers: [[Move::null_move(); KILLER_SLOTS]; MAX_PLY],
+            history: [[0; 64]; 64],
+            pv_table: [[Move::null_move(); MAX_PLY]; MAX_PLY],
+            pv_length: [0; MAX_PLY],
+            nodes: Arc::new(AtomicU64::new(0)),
+            seldepth: 0,
+            config: SearchConfig::DEFAULT,
+            deadline: None,
+            should_search: Arc::new(AtomicBool::new(true)),
+            eval_params: EvalParams::DEFAULT,
+            pawn_table: PawnTable::new(),
+        }
+    }
+
+    /// Forgets the killers of the last search and ages the history, so a new position
+    /// is not ordered by what mattered many moves ago.
+    pub fn new_search(&mut self) {
+        self.killers = [[Move::null_move(); KILLER_SLOTS]; MAX_PLY];
+        for from in self.history.iter_mut() {
+            for score in from.iter_mut() {
+                *score /= 2;
+            }
+        }
+        self.nodes.store(0, Ordering::Relaxed);
+    }
+
+    /// The number of positions searched since `new_search`.
+    pub fn nodes(&self) -> u64 {
+        self.nodes.load(Ordering::Relaxed)
+    }
+
+    /// A handle to read `nodes` from another thread, while this context is searching.
+    pub fn node_counter(&self) -> Arc<AtomicU64> {
+        Arc::clone(&self.nodes)
+    }
+
+    /// The deepest ply the last iteration reached, including quiescence search.
+    pub fn seldepth(&self) -> usize {
+        self.seldepth
+    }
+
+    fn principal_variation(&self) -> Vec<Move> {
+        self.pv_table[0][..self.pv_length[0]].to_vec()
+    }
+
+    /// Whether the search has to stop, because it was told to or, checked every few thousand
+    /// nodes, because the deadline passed. Running out of time also clears `should_search`,
+    /// so the other threads stop as well.
+    fn should_stop(&self) -> bool {
+        if self.nodes().is_multiple_of(TIME_CHECK_INTERVAL)
+            && let Some(deadline) = self.deadline
+            && Instant::now() >= deadline
+        {
+            self.should_search.store(false, Ordering::Relaxed);
+        }
+        !self.should_search.load(Ordering::Relaxed)
+    }
+
+    fn count_node(&mut self, ply: usize) {
+        // No other thread writes the counter, so there is no need for a `fetch_add`
+        self.nodes.store(self.nodes() + 1, Ordering::Relaxed);
+        self.seldepth = self.seldepth.max(ply);
+    }
+
+    /// Called when a node is entered, so it never passes on the line of an earlier sibling.
+    fn clear_pv(&mut self, ply: usize) {
+        if ply < MAX_PLY {
+            self.pv_length[ply] = ply;
+        }
+    }
+
+    /// `_move` is the new best move at `ply`, followed by the best line of its child.
+    fn update_pv(&mut self, ply: usize, _move: Move) {
+        if ply >= MAX_PLY {
+            return;
+        }
+        self.pv_table[ply][ply] = _move;
+        let child_length = if ply + 1 < MAX_PLY {
+            self.pv_length[ply + 1]
+        } else {
+            ply + 1
+        };
+        for i in ply + 1..child_length {
+            self.pv_table[ply][i] = self.pv_table[ply + 1][i];
+        }
+        self.pv_length[ply] = child_length.max(ply + 1);
+    }
+
+    fn is_killer(&self, ply: usize, _move: &Move) -> bool {
+        ply < MAX_PLY && self.killers[ply].contains(_move)
+    }
+
+    fn history_score(&self, _move: &Move) -> u32 {
+        self.history[_move.from()][_move.to()]
+    }
+
+    /// Remembers a quiet move that caused a beta cutoff.
+    fn store_cutoff(&mut self, ply: usize, _move: Move, depth: u8) {
+        if ply < MAX_PLY && self.killers[ply][0] != _move {
+            self.killers[ply][1] = self.killers[ply][0];
+            self.killers[ply][0] = _move;
+        }
+        let history = &mut self.history[_move.from()][_move.to()];
+        *history = history.saturating_add(depth as u32 * depth as u32);
+    }
+}
+
+impl Default for SearchContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The outcome of a search at one depth.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchResult {
+    pub best_move: Move,
+    /// The score of the position from white's point of view.
+    pub score: i16,
+    /// The principal variation, starting with `best_move`.
+    pub pv: Vec<Move>,
+}
+
+/// Searches the position to `depth`. If the score of the previous iteration is known, the
+/// search starts with a narrow aspiration window around it, which is widened on the side that
+/// failed until the score is inside.
 pub fn search_entry(
-    board: &Board,
+    board: &mut Board,
     depth: u8,
-    tt_table: &mut TT_Table,
+    previous_score: Option<i16>,
+    tt_table: &TT_Table,
+    search_context: &mut SearchContext,
     repetition_lookup: &mut [u64; 100],
     num_resetting_moves: u8,
-    should_search: &Arc<AtomicBool>,
-) -> Option<Move> {
+) -> Option<SearchResult> {
+    search_context.seldepth = 0;
+    let root = Node {
+        depth,
+        ply: 0,
+        alpha: MIN_SCORE,
+        beta: MAX_SCORE,
+        num_resetting_moves,
+    };
+    let Some(previous_score) = previous_score else {
+        return search_root(board, root, tt_table, search_context, repetition_lookup);
+    };
+
+    let mut alpha_delta = ASPIRATION_WINDOW;
+    let mut beta_delta = ASPIRATION_WINDOW;
+    loop {
+        let alpha = window_bound(previous_score, -alpha_delta);
+        let beta = window_bound(previous_score, beta_delta);
+        let result = search_root(
+            board,
+            root.with_window(alpha, beta),
+            tt_table,
+            search_context,
+            repetition_lookup,
+        )?;
+
+        if result.score <= alpha && alpha > MIN_SCORE {
+            alpha_delta = alpha_delta.saturating_mul(4);
+        } else if result.score >= beta && beta < MAX_SCORE {
+            beta_delta = beta_delta.saturating_mul(4);
+        } else {
+            return Some(result);
+        }
+        SearchMetrics::increment_aspiration_re_searches();
+    }
+}
+
+fn window_bound(score: i16, delta: i16) -> i16 {
+    if delta <= -ASPIRATION_FULL_WINDOW {
+        return MIN_SCORE;
+    }
+    if delta >= ASPIRATION_FULL_WINDOW {
+        return MAX_SCORE;
+    }
+    (score as i32 + delta as i32).clamp(MIN_SCORE as i32, MAX_SCORE as i32) as i16
+}
+
+/// The number of moves until mate, if `score` is a mate score. Positive if the side `score`
+/// is relative to delivers the mate, negative if it gets mated.
+pub fn mate_in(score: i16) -> Option<i16> {
+    if is_white_mate(score) {
+        Some((WHITE_WINS - score + 1) / 2)
+    } else if is_black_mate(score) {
+        Some(-((score - BLACK_WINS + 1) / 2))
+    } else {
+        None
+    }
+}
+
+// MIN_SCORE and MAX_SCORE are only window bounds, not mates
+fn is_white_mate(score: i16) -> bool {
+    (MATE_THRESHOLD..=WHITE_WINS).contains(&score)
+}
+
+fn is_black_mate(score: i16) -> bool {
+    (BLACK_WINS..=-MATE_THRESHOLD).contains(&score)
+}
+
+fn is_mate(score: i16) -> bool {
+    is_white_mate(score) || is_black_mate(score)
+}
+
+/// Mate scores are stored relative to the node instead of the root, because the same position
+/// can be reached at a different ply.
+fn score_to_tt(score: i16, ply: usize) -> i16 {
+    if is_white_mate(score) {
+        score + ply as i16
+    } else if is_black_mate(score) {
+        score - ply as i16
+    } else {
+        score
+    }
+}
+
+/// Mate distance pruning: no score at `ply` can be better than mating right here, or worse
+/// than getting mated right here. Returns the bound to return if the window is empty after that.
+/// This also keeps the scores of a node in the range `score_to_tt` can store.
+fn mate_distance_window(
+    alpha: &mut i16,
+    beta: &mut i16,
+    ply: usize,
+    maximize_score: bool,
+) -> Option<i16> {
+    *alpha = (*alpha).max(BLACK_WINS + ply as i16);
+    *beta = (*beta).min(WHITE_WINS - ply as i16);
+    if *alpha >= *beta {
+        Some(if maximize_score { *alpha } else { *beta })
+    } else {
+        None
+    }
+}
+
+/// Whether null move pruning may be tried. Not in check, where passing is illegal, and not with
+/// only pawns left, where zugzwang makes passing better than every move.
+fn can_null_move(board: &Board, depth: u8, in_check: bool) -> bool {
+    depth > NULL_MOVE_REDUCTION && !in_check && board.has_non_pawn_material(board.white_to_move)
+}
+
+/// Whether a node is searched with a whole window, not a null window that only proves a bound.
+fn is_pv_node(alpha: i16, beta: i16) -> bool {
+    beta as i32 - alpha as i32 > 1
+}
+
+fn score_from_tt(score: i16, ply: usize) -> i16 {
+    if is_white_mate(score) {
+        score - ply as i16
+    } else if is_black_mate(score) {
+        score + ply as i16
+    } else {
+        score
+    }
+}
+
+fn search_root(
+    board: &mut Board,
+    root: Node,
+    tt_table: &TT_Table,
+    search_context: &mut SearchContext,
+    repetition_lookup: &mut [u64; 100],
+) -> Option<SearchResult> {
+    let Node {
+        depth,
+        mut alpha,
+        mut beta,
+        num_resetting_moves,
+        ..
+    } = root;
     SearchMetrics::increment_normal_search_entries();
     // Initialize metrics if not already done
+    search_context.clear_pv(0);
+    search_context.count_node(0);
 
     SearchMetrics::change_timing_kind(TimingKind::Search);
     let maximize_score = board.white_to_move;
@@ -37,56 +421,41 @@ pub fn search_entry(
 
     SearchMetrics::increment_normal_search_positions_generated(legal_moves.len() as u64);
 
-    let mut best_move = None;
+    let mut best_move = legal_moves[0];
 
     let mut best_score = if maximize_score { MIN_SCORE } else { MAX_SCORE };
-
-    let mut alpha = MIN_SCORE;
-    let mut beta = MAX_SCORE;
+    let (window_alpha, window_beta) = (alpha, beta);
 
     sort_moves(
         &mut legal_moves,
+        None,
         board,
-        maximize_score,
         tt_table,
-        alpha,
-        beta,
+        root,
+        search_context,
     );
 
-    for _move in legal_moves {
-        if should_search.load(Ordering::Relaxed) == false {
+    for (i, &_move) in legal_moves.iter().enumerate() {
+        if !search_context.should_search.load(Ordering::Relaxed) {
             return None;
         }
-        let new_board = board.make_move_temp(&_move);
-        let new_num_resetting_moves = if _move.resets_clock(board) {
-            num_resetting_moves + 1
-        } else {
-            num_resetting_moves
-        };
-        let score = if _move.resets_clock(board) {
-            min_max_search(
-                &new_board,
-                depth,
-                alpha,
-                beta,
-                tt_table,
-                &mut [board.zobrist_hash; 100],
-                new_num_resetting_moves,
-                should_search,
-            )
-        } else {
-            repetition_lookup[(board.halfmove_clock + 1) as usize] = board.zobrist_after(&_move);
-            min_max_search(
-                &new_board,
-                depth,
+        let score = search_move(
+            board,
+            _move,
+            MoveFlags {
+                is_first_move: i == 0,
+                quiet_move: QuietMove::FullDepth,
+            },
+            Node {
+                ply: 1,
                 alpha,
                 beta,
-                tt_table,
-                repetition_lookup,
-                new_num_resetting_moves,
-                should_search,
-            )
-        };
+                ..root
+            },
+            tt_table,
+            search_context,
+            repetition_lookup,
+        );
 
         if score == SEARCH_CANCELED {
             return None;
@@ -94,53 +463,186 @@ pub fn search_entry(
 
         if maximize_score && score > best_score {
             best_score = score;
-            best_move = Some(_move);
+            best_move = _move;
             alpha = best_score.max(alpha);
+            search_context.update_pv(0, _move);
         } else if !maximize_score && score < best_score {
             best_score = score;
-            best_move = Some(_move);
+            best_move = _move;
             beta = best_score.min(beta);
+            search_context.update_pv(0, _move);
+        }
+        // only possible with an aspiration window, which has to be widened anyway
+        if beta <= alpha {
+            return Some(SearchResult {
+                best_move,
+                score: best_score,
+                pv: search_context.principal_variation(),
+            });
         }
     }
     // we store depth + 1, because we pass it directly to minmax search
     // all Root Nodes are pv nodes, because a beta cutoff can never occur and alpha is always raised
     // see: https://www.chessprogramming.org/Node_Types#PV-Nodes
-    tt_table.insert(
-        board.zobrist_hash,
-        best_score,
-        depth + 1,
-        NodeType::PvNode,
-        best_move.unwrap(),
-        board.halfmove_clock,
-        num_resetting_moves,
-    );
-    best_move
+    // (a root that failed low on its aspiration window is searched again, so it is not stored)
+    let failed_low = (maximize_score && best_score <= window_alpha && window_alpha > MIN_SCORE)
+        || (!maximize_score && best_score >= window_beta && window_beta < MAX_SCORE);
+    if !failed_low {
+        tt_table.insert(
+            board.zobrist_hash,
+            best_score,
+            depth + 1,
+            NodeType::PvNode,
+            best_move,
+            board.halfmove_clock,
+            num_resetting_moves,
+        );
+    }
+    Some(SearchResult {
+        best_move,
+        score: best_score,
+        pv: search_context.principal_variation(),
+    })
+}
+
+/// Makes `_move`, searches it with principal variation search and unmakes it again.
+/// Only the first move gets the whole window. Every later move is expected to be worse and
+/// only has to be proven so with a null window, it is searched again with the whole window
+/// if it turns out to be better after all.
+/// Unless it gives check, a quiet move is first searched with a reduced depth or not at all,
+/// as `quiet_move` says. A futile move scores the bound of the window, so it is never best.
+/// `child` is the node after the move.
+fn search_move(
+    board: &mut Board,
+    _move: Move,
+    flags: MoveFlags,
+    child: Node,
+    tt_table: &TT_Table,
+    search_context: &mut SearchContext,
+    repetition_lookup: &mut [u64; 100],
+) -> i16 {
+    let MoveFlags {
+        is_first_move,
+        quiet_move,
+    } = flags;
+    let Node {
+        depth, alpha, beta, ..
+    } = child;
+    let maximize_score = board.white_to_move;
+    let resets_clock = _move.resets_clock(board);
+    let child = Node {
+        num_resetting_moves: child.num_resetting_moves + resets_clock as u8,
+        ..child
+    };
+    let undo_info = board.make_move(_move);
+    if !resets_clock {
+        repetition_lookup[(undo_info.halfmove_clock + 1) as usize] = board.zobrist_hash;
+    }
+
+    let gives_check = quiet_move != QuietMove::FullDepth && board.in_check_temp();
+    if quiet_move == QuietMove::Futile && !gives_check {
+        board.unmake_move(_move, undo_info);
+        SearchMetrics::increment_futility_pruned_moves();
+        return if maximize_score { alpha } else { beta };
+    }
+
+    let mut search_window = |board: &mut Board, depth: u8, alpha: i16, beta: i16| {
+        // a move that resets the clock can never be repeated, so the history starts fresh
+        let mut fresh_lookup = [undo_info.zobrist_hash; 100];
+        min_max_search(
+            board,
+            Node {
+                depth,
+                alpha,
+                beta,
+                ..child
+            },
+            tt_table,
+            search_context,
+            if resets_clock {
+                &mut fresh_lookup
+            } else {
+                &mut *repetition_lookup
+            },
+            true,
+        )
+    };
+
+    let mut score;
+    if is_first_move {
+        score = search_window(board, depth, alpha, beta);
+    } else {
+        let (null_alpha, null_beta) = if maximize_score {
+            (alpha, alpha + 1)
+        } else {
+            (beta - 1, beta)
+        };
+        score = if quiet_move == QuietMove::Reduced && !gives_check {
+            SearchMetrics::increment_late_move_reductions();
+            let reduced_score = search_window(board, depth - LMR_REDUCTION, null_alpha, null_beta);
+            let beats_alpha = if maximize_score {
+                reduced_score > alpha
+            } else {
+                reduced_score < beta
+            };
+            if reduced_score != SEARCH_CANCELED && beats_alpha {
+                SearchMetrics::increment_late_move_re_searches();
+                search_window(board, depth, null_alpha, null_beta)
+            } else {
+                reduced_score
+            }
+        } else {
+            search_window(board, depth, null_alpha, null_beta)
+        };
+        if score != SEARCH_CANCELED && score > alpha && score < beta {
+            SearchMetrics::increment_pvs_re_searches();
+            score = search_window(board, depth, alpha, beta);
+        }
+    }
+    board.unmake_move(_move, undo_info);
+    score
 }
 
 fn min_max_search(
-    board: &Board,
-    depth: u8,
-    mut alpha: i16,
-    mut beta: i16,
-    tt_table: &mut TT_Table,
+    board: &mut Board,
+    node: Node,
+    tt_table: &TT_Table,
+    search_context: &mut SearchContext,
     //TODO: look if board can also be used
     repetition_lookup: &mut [u64; 100],
-    num_resetting_moves: u8,
-    should_search: &Arc<AtomicBool>,
+    allow_null_move: bool,
 ) -> i16 {
-    if should_search.load(Ordering::Relaxed) == false {
+    let Node {
+        depth,
+        ply,
+        mut alpha,
+        mut beta,
+        num_resetting_moves,
+    } = node;
+    if search_context.should_stop() {
         return SEARCH_CANCELED;
     }
+    search_context.clear_pv(ply);
+    if let Some(bound) = mate_distance_window(&mut alpha, &mut beta, ply, board.white_to_move) {
+        return bound;
+    }
+    // Check extension: a position in check is searched one ply deeper, so forcing lines
+    // are not cut off in the middle and quiescence search never starts in check.
+    let in_check = board.in_check_temp();
+    let depth = if in_check && ply < MAX_PLY {
+        SearchMetrics::increment_check_extensions();
+        depth + 1
+    } else {
+        depth
+    };
     if depth == 0 {
         SearchMetrics::change_timing_kind(TimingKind::QSearch);
         let q_search_score = q_search(
             board,
-            alpha,
-            beta,
+            node.with_window(alpha, beta),
             tt_table,
+            search_context,
             repetition_lookup,
-            num_resetting_moves,
-            should_search,
         );
         SearchMetrics::change_timing_kind(TimingKind::Search);
         return q_search_score;
@@ -149,6 +651,7 @@ fn min_max_search(
     SearchMetrics::change_timing_kind(TimingKind::Search);
 
     SearchMetrics::increment_normal_search_entries();
+    search_context.count_node(ply);
 
     let maximize_score = board.white_to_move;
 
@@ -165,9 +668,9 @@ fn min_max_search(
     let mut legal_moves = board.generate_legal_moves_temp();
 
     match check_game_result::<false>(board, repetition_lookup, legal_moves.len()) {
-        GameResult::WhiteWins => return WHITE_WINS,
+        GameResult::WhiteWins => return WHITE_WINS - ply as i16,
 
-        GameResult::BlackWins => return BLACK_WINS,
+        GameResult::BlackWins => return BLACK_WINS + ply as i16,
 
         GameResult::Draw(_) => return 0,
 
@@ -179,8 +682,9 @@ fn min_max_search(
         SearchMetrics::increment_normal_search_tt_hits();
         //TODO: increment in move ordering
         // SearchMetrics::increment_normal_search_tt_hits();
-        if tt_hit.depth() >= depth {
-            let tt_score = tt_hit.eval();
+        // A cutoff at a PV node would leave the principal variation cut short here
+        if tt_hit.depth() >= depth && !is_pv_node(alpha, beta) {
+            let tt_score = score_from_tt(tt_hit.eval(), ply);
             match tt_hit.node_type() {
                 NodeType::PvNode => {
                     SearchMetrics::increment_normal_search_tt_cutoffs();
@@ -205,15 +709,96 @@ fn min_max_search(
         }
     }
 
+    let config = search_context.config;
+    // the static evaluation is only needed for the pruning close to the leaves
+    let static_eval = if !in_check
+        && ((config.reverse_futility_pruning && depth <= REVERSE_FUTILITY_MAX_DEPTH)
+            || (config.futility_pruning && (depth as usize) < FUTILITY_MARGINS.len()))
+    {
+        SearchMetrics::change_timing_kind(TimingKind::Evaluation);
+        let static_eval = eval(
+            board,
+            &search_context.eval_params,
+            &mut search_context.pawn_table,
+        );
+        SearchMetrics::change_timing_kind(TimingKind::Search);
+        Some(static_eval)
+    } else {
+        None
+    };
+
+    // Pruning returns the bound the node fails against. Only a searched line can prove a mate,
+    // so a mate bound is never pruned against, and neither are PV nodes, which need exact scores.
+    let cutoff_bound = if maximize_score { beta } else { alpha };
+    let can_prune = !is_pv_node(alpha, beta) && !is_mate(cutoff_bound);
+
+    // Reverse futility pruning: if the position is so good that even losing a margin per
+    // remaining ply still fails high, the search is not expected to change that.
+    if config.reverse_futility_pruning
+        && can_prune
+        && depth <= REVERSE_FUTILITY_MAX_DEPTH
+        && let Some(static_eval) = static_eval
+    {
+        let margin = REVERSE_FUTILITY_MARGIN * depth as i16;
+        if maximize_score && static_eval.saturating_sub(margin) >= beta {
+            SearchMetrics::increment_reverse_futility_cutoffs();
+            return beta;
+        }
+        if !maximize_score && static_eval.saturating_add(margin) <= alpha {
+            SearchMetrics::increment_reverse_futility_cutoffs();
+            return alpha;
+        }
+    }
+
+    // Null move pruning: if passing the turn still fails high with a reduced null window search,
+    // a real move will too. Not used twice in a row.
+    if allow_null_move && can_prune && can_null_move(board, depth, in_check) {
+        let (null_alpha, null_beta) = if maximize_score {
+            (beta - 1, beta)
+        } else {
+            (alpha, alpha + 1)
+        };
+        let undo_info = board.make_null_move();
+        // a null move can never be repeated, so the repetition history starts fresh like
+        // after a capture
+        let score = min_max_search(
+            board,
+            Node {
+                depth: depth - 1 - NULL_MOVE_REDUCTION,
+                ply: ply + 1,
+                alpha: null_alpha,
+                beta: null_beta,
+                num_resetting_moves,
+            },
+            tt_table,
+            search_context,
+            &mut [undo_info.zobrist_hash; 100],
+            false,
+        );
+        board.unmake_null_move(undo_info);
+
+        if score == SEARCH_CANCELED {
+            return SEARCH_CANCELED;
+        }
+        if maximize_score && score >= beta {
+            SearchMetrics::increment_null_move_cutoffs();
+            return beta;
+        }
+        if !maximize_score && score <= alpha {
+            SearchMetrics::increment_null_move_cutoffs();
+            return alpha;
+        }
+    }
+
     SearchMetrics::change_timing_kind(TimingKind::NormalMoveOrdering);
 
     sort_moves(
         &mut legal_moves,
+        None,
         board,
-        maximize_score,
         tt_table,
-        alpha,
-        beta,
+        node.with_window(alpha, beta),
+        search_context,
     );
 
     SearchMetrics::increment_normal_search_positions_generated(legal_moves.len() as u64);
@@ -225,39 +810,50 @@ fn min_max_search(
     let mut node_type = NodeType::AllNode;
     let mut best_move: Move = Move::null_move();
 
+    // Futility pruning: if even a big positional gain can not raise the static evaluation
+    // above alpha, quiet moves are not searched. Captures and checks still might.
+    let futile = config.futility_pruning
+        && (depth as usize) < FUTILITY_MARGINS.len()
+        && static_eval.is_some_and(|static_eval| {
+            let margin = FUTILITY_MARGINS[depth as usize];
+            if maximize_score {
+                static_eval.saturating_add(margin) <= alpha
+            } else {
+                static_eval.saturating_sub(margin) >= beta
+            }
+        });
+    let can_reduce = config.late_move_reductions && depth >= LMR_MIN_DEPTH && !in_check;
+
     let mut i = 0;
     while i < legal_moves.len() {
         let _move = legal_moves[i];
-        let new_game = board.make_move_temp(&_move);
-        let new_num_resetting_moves = if _move.resets_clock(board) {
-            num_resetting_moves + 1
+        let is_quiet =
+            !_move.is_capture() && !_move.is_promotion() && !search_context.is_killer(ply, &_move);
+        let quiet_move = if is_quiet && i > 0 && futile {
+            QuietMove::Futile
+        } else if is_quiet && i >= LMR_FULL_DEPTH_MOVES && can_reduce {
+            QuietMove::Reduced
         } else {
-            num_resetting_moves
+            QuietMove::FullDepth
         };
-        let score = if _move.resets_clock(board) {
-            min_max_search(
-                &new_game,
-                depth - 1,
-                alpha,
-                beta,
-                tt_table,
-                &mut [board.zobrist_hash; 100],
-                new_num_resetting_moves,
-                should_search,
-            )
-        } else {
-            repetition_lookup[(board.halfmove_clock + 1) as usize] = board.zobrist_after(&_move);
-            min_max_search(
-                &new_game,
-                depth - 1,
+        let score = search_move(
+            board,
+            _move,
+            MoveFlags {
+                is_first_move: i == 0,
+                quiet_move,
+            },
+            Node {
+                depth: depth - 1,
+                ply: ply + 1,
                 alpha,
                 beta,
-                tt_table,
-                repetition_lookup,
-                new_num_resetting_moves,
-                should_search,
-            )
-        };
+                num_resetting_moves,
+            },
+            tt_table,
+            search_context,
+            repetition_lookup,
+        );
         if score == SEARCH_CANCELED {
             return SEARCH_CANCELED;
         }
@@ -270,6 +866,7 @@ fn min_max_search(
             best_move = _move;
             best_score = score;
             node_type = NodeType::PvNode;
+            search_context.update_pv(ply, _move);
 
             if maximize_score {
                 alpha = best_score.max(alpha);
@@ -282,6 +879,14 @@ fn min_max_search(
                 // The move that caused the cutoff is at index 'i'. We add its 1-based index.
                 SearchMetrics::add_to_normal_search_sum_of_cutoff_indices((i + 1) as u64);
                 SearchMetrics::increment_normal_search_cutoffs();
+                if !_move.is_capture() {
+                    if search_context.is_killer(ply, &_move) {
+                        SearchMetrics::increment_killer_move_cutoffs();
+                    } else if search_context.history_score(&_move) > 0 {
+                        SearchMetrics::increment_history_heuristic_cutoffs();
+                    }
+                    search_context.store_cutoff(ply, _move, depth);
+                }
                 break; // Beta cut-off
             }
         }
@@ -290,7 +895,7 @@ fn min_max_search(
 
     tt_table.insert(
         board.zobrist_hash,
-        best_score,
+        score_to_tt(best_score, ply),
         depth,
         node_type,
         best_move,
@@ -311,17 +916,27 @@ fn min_max_search(
 }
 
 fn q_search(
-    board: &Board,
-    mut alpha: i16,
-    mut beta: i16,
-    tt_table: &mut TT_Table,
+    board: &mut Board,
+    node: Node,
+    tt_table: &TT_Table,
+    search_context: &mut SearchContext,
     repetition_lookup: &mut [u64; 100],
-    num_resetting_moves: u8,
-    should_search: &Arc<AtomicBool>,
 ) -> i16 {
-    if should_search.load(Ordering::Relaxed) == false {
+    let Node {
+        ply,
+        mut alpha,
+        mut beta,
+        num_resetting_moves,
+        ..
+    } = node;
+    if search_context.should_stop() {
         return SEARCH_CANCELED;
     }
+    search_context.clear_pv(ply);
+    search_context.count_node(ply);
+    if let Some(bound) = mate_distance_window(&mut alpha, &mut beta, ply, board.white_to_move) {
+        return bound;
+    }
     SearchMetrics::increment_q_search_entries();
 
     SearchMetrics::change_timing_kind(TimingKind::QSearch);
@@ -329,7 +944,11 @@ fn q_search(
     let maximize_score = board.white_to_move;
 
     SearchMetrics::change_timing_kind(TimingKind::Evaluation);
-    let stand_pat = eval(board);
+    let stand_pat = eval(
+        board,
+        &search_context.eval_params,
+        &mut search_context.pawn_table,
+    );
     SearchMetrics::change_timing_kind(TimingKind::QSearch);
 
     //TODO: not in check
@@ -353,11 +972,9 @@ fn q_search(
     SearchMetrics::increment_q_search_positions_generated(legal_moves.len() as u64);
 
     match check_game_result::<false>(board, repetition_lookup, legal_moves.len()) {
-        //TODO:
-        GameResult::WhiteWins => return WHITE_WINS,
+        GameResult::WhiteWins => return WHITE_WINS - ply as i16,
 
-        //TODO:
-        GameResult::BlackWins => return BLACK_WINS,
+        GameResult::BlackWins => return BLACK_WINS + ply as i16,
 
         GameResult::Draw(_) => return 0,
 
@@ -365,12 +982,14 @@ fn q_search(
     }
 
     SearchMetrics::increment_q_search_tt_probes();
-    if let Some(tt_hit) = tt_table.probe(board.zobrist_hash) {
+    if let Some(tt_hit) = tt_table.probe(board.zobrist_hash)
+        && !is_pv_node(alpha, beta)
+    {
         //TODO: increment in move ordering
         // SearchMetrics::increment_normal_search_tt_hits();
         SearchMetrics::increment_q_search_tt_hits();
         //
-        let tt_score = tt_hit.eval();
+        let tt_score = score_from_tt(tt_hit.eval(), ply);
         match tt_hit.node_type() {
             NodeType::PvNode => {
                 SearchMetrics::increment_q_search_tt_cutoffs();
@@ -391,21 +1010,27 @@ fn q_search(
         }
     }
 
-    let mut legal_captures: MoveList = legal_moves
-        .iter()
-        .filter(|m| m.is_capture())
-        .copied()
-        .collect();
+    // Captures that lose material are pruned, the stand pat score is at least as good.
+    // The exchange evaluation of the others is kept for ordering them.
+    let mut legal_captures = MoveList::default();
+    let mut see_scores: ArrayVec<i16, MAX_NUM_MOVES> = ArrayVec::new();
+    for &_move in legal_moves.iter().filter(|m| m.is_capture()) {
+        let see = board.see(&_move);
+        if see >= 0 {
+            legal_captures.push(_move);
+            see_scores.push(see);
+        }
+    }
 
     SearchMetrics::change_timing_kind(TimingKind::QMoveOrdering);
 
     sort_moves(
         &mut legal_captures,
+        Some(&see_scores),
         board,
-        maximize_score,
         tt_table,
-        alpha,
-        beta,
+        node.with_window(alpha, beta),
+        search_context,
     );
 
     SearchMetrics::change_timing_kind(TimingKind::QSearch);
@@ -421,34 +1046,28 @@ fn q_search(
     let mut i = 0;
     while i < legal_captures.len() {
         let _move = legal_captures[i];
-        let new_bard = board.make_move_temp(&_move);
-        let new_num_resetting_moves = if _move.resets_clock(board) {
-            num_resetting_moves + 1
-        } else {
-            num_resetting_moves
+        let resets_clock = _move.resets_clock(board);
+        let child = Node {
+            depth: 0,
+            ply: ply + 1,
+            alpha,
+            beta,
+            num_resetting_moves: num_resetting_moves + resets_clock as u8,
         };
-        let score = if _move.resets_clock(board) {
+        let undo_info = board.make_move(_move);
+        let score = if resets_clock {
             q_search(
-                &new_bard,
-                alpha,
-                beta,
+                board,
+                child,
                 tt_table,
-                &mut [board.zobrist_hash; 100],
-                new_num_resetting_moves,
-                should_search,
+                search_context,
+                &mut [undo_info.zobrist_hash; 100],
             )
         } else {
-            repetition_lookup[(board.halfmove_clock + 1) as usize] = board.zobrist_after(&_move);
-            q_search(
-                &new_bard,
-                alpha,
-                beta,
-                tt_table,
-                repetition_lookup,
-                new_num_resetting_moves,
-                should_search,
-            )
+            repetition_lookup[(undo_info.halfmove_clock + 1) as usize] = board.zobrist_hash;
+            q_search(board, child, tt_table, search_context, repetition_lookup)
         };
+        board.unmake_move(_move, undo_info);
 
         if score == SEARCH_CANCELED {
             return SEARCH_CANCELED;
@@ -461,6 +1080,7 @@ fn q_search(
             node_type = NodeType::PvNode;
             best_score = score;
             best_move = _move;
+            search_context.update_pv(ply, _move);
             // The current move's index is `i`.
             #[cfg(feature = "metrics")]
             {
@@ -488,7 +1108,7 @@ fn q_search(
 
     tt_table.insert(
         board.zobrist_hash,
-        best_score,
+        score_to_tt(best_score, ply),
         0,
         node_type,
         best_move,
@@ -510,17 +1130,23 @@ fn q_search(
     best_score
 }
 
+/// Orders `moves` with the most promising first. A caller that already knows the static exchange
+/// evaluation of the moves passes them in `see_scores`, in the order of `moves`.
 fn sort_moves(
     moves: &mut MoveList,
+    see_scores: Option<&[i16]>,
     board: &Board,
-    is_maximizing: bool,
     tt_table: &TT_Table,
-    alpha: i16,
-    beta: i16,
+    node: Node,
+    search_context: &SearchContext,
 ) {
+    let is_maximizing = board.white_to_move;
+    let Node {
+        ply, alpha, beta, ..
+    } = node;
     let hash_move_option = tt_table.probe(board.zobrist_hash).map(|h| h.best_move());
 
-    moves.sort_by_cached_key(|m| {
+    let order_key = |index: usize, m: &Move| {
         const HASH_MOVE_SCORE: i32 = 8_000_000;
         //lok at graph http://www.netlib.org/utk/lsi/pcwLSI/text/node351.html
         // pv node, most left, then cut nodes should be preffered, all nodes hould be searched last
@@ -529,6 +1155,9 @@ fn sort_moves(
         const CUT_MOVE_SCORE: i32 = 1_500_000;
         const ALL_MOVE_SCORE: i32 = -1_000_000;
         const CAPTURE_BASE_SCORE: i32 = 500_000;
+        const KILLER_MOVE_SCORE: i32 = 400_000;
+        // history scores are capped below the killers
+        const MAX_HISTORY_SCORE: i32 = 300_000;
 
         if let Some(Some(hash_move)) = hash_move_option {
             if *m == hash_move {
@@ -559,13 +1188,38 @@ fn sort_moves(
         // --- HIERARCHY LEVEL 2: CAPTURES ---
         if score == 0 && m.is_capture() {
             // Only check if not already a PV move
-            let victim_value = pieces_score(board.pieces[m.to()]).abs() as i32;
-            let attacker_value = pieces_score(board.pieces[m.from()]).abs() as i32;
-
-            score = CAPTURE_BASE_SCORE + (victim_value * 10 - attacker_value);
+            let see = see_scores.map_or_else(|| board.see(m), |scores| scores[index]) as i32;
+            score = if see >= 0 {
+                let victim_value = pieces_score(board.pieces[m.to()]).abs() as i32;
+                let attacker_value = pieces_score(board.pieces[m.from()]).abs() as i32;
+                // MVV-LVA only breaks ties between captures that win the same material
+                CAPTURE_BASE_SCORE + see * 100 + (victim_value * 10 - attacker_value)
+            } else {
+                // losing captures go after the quiet moves
+                see
+            };
+        }
+        // --- HIERARCHY LEVEL 3: QUIET MOVES ---
+        if score == 0 && !m.is_capture() {
+            score = if search_context.is_killer(ply, m) {
+                KILLER_MOVE_SCORE
+            } else {
+                (search_context.history_score(m) as i32).min(MAX_HISTORY_SCORE)
+            };
         }
         -score
-    });
+    };
+
+    // stable, so moves with the same key keep the order of the move generator
+    let mut keyed_moves: ArrayVec<(i32, Move), MAX_NUM_MOVES> = moves
+        .iter()
+        .enumerate()
+        .map(|(index, m)| (order_key(index, m), *m))
+        .collect();
+    keyed_moves.sort_by_key(|&(key, _)| key);
+    for (slot, (_, m)) in moves.iter_mut().zip(keyed_moves) {
+        *slot = m;
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -645,3 +1299,132 @@ pub fn check_game_result<const DETECT_THREE_FOLD: bool>(
 
     GameResult::Ongoing
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_can_null_move() {
+        let board = Board::from_fen("4k3/pppp4/8/8/8/8/PPPP4/2N1K3 w - - 0 1").unwrap();
+        assert!(can_null_move(&board, 4, false));
+        assert!(!can_null_move(&board, NULL_MOVE_REDUCTION, false));
+        assert!(!can_null_move(&board, 4, true));
+
+        // black only has pawns, passing could escape a zugzwang
+        let board = Board::from_fen("4k3/pppp4/8/8/8/8/PPPP4/2N1K3 b - - 0 1").unwrap();
+        assert!(!can_null_move(&board, 4, false));
+
+        let board = Board::from_fen("4k3/pppp4/8/8/8/8/PPPP4/2N1K2r w - - 0 1").unwrap();
+        assert!(board.in_check_temp());
+        assert!(!can_null_move(&board, 4, board.in_check_temp()));
+    }
+
+    #[test]
+    fn test_killer_and_history_moves_are_ordered_before_other_quiet_moves() {
+        let board = Board::from_fen("4k3/8/8/p7/8/8/8/R3K3 w - - 0 1").unwrap();
+        let find = |uci: &str| {
+            *board
+                .generate_legal_moves_temp()
+                .iter()
+                .find(|m| m.to_uci() == uci)
+                .unwrap()
+        };
+        let node = Node {
+            depth: 4,
+            ply: 3,
+            alpha: MIN_SCORE,
+            beta: MAX_SCORE,
+            num_resetting_moves: 0,
+        };
+        let mut search_context = SearchContext::new();
+        // a cutoff at another ply only counts for the history
+        search_context.store_cutoff(5, find("e1f2"), 2);
+        search_context.store_cutoff(3, find("a1a4"), 1);
+
+        let mut moves = board.generate_legal_moves_temp();
+        sort_moves(
+            &mut moves,
+            None,
+            &board,
+            &TT_Table::with_size_mb(1),
+            node,
+            &search_context,
+        );
+        let order: Vec<String> = moves.iter().map(Move::to_uci).collect();
+        // the capture, then the killer, then the move with the highest history score
+        assert_eq!(order[..3], ["a1a5", "a1a4", "e1f2"]);
+    }
+
+    fn search_to_depth(fen: &str, depth: u8) -> SearchResult {
+        let mut board = Board::from_fen(fen).unwrap();
+        let mut previous_score = None;
+        let mut result = None;
+        let tt_table = TT_Table::with_size_mb(1);
+        let mut search_context = SearchContext::new();
+        for depth in 0..=depth {
+            let searched = search_entry(
+                &mut board,
+                depth,
+                previous_score,
+                &tt_table,
+                &mut search_context,
+                &mut [0; 100],
+                0,
+            )
+            .unwrap();
+            previous_score = Some(searched.score);
+            result = Some(searched);
+        }
+        result.unwrap()
+    }
+
+    #[test]
+    fn test_mate_distance_scores() {
+        // white mates with Ra8 on the next ply
+        let result = search_to_depth("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 2);
+        assert_eq!(result.score, WHITE_WINS - 1);
+        assert_eq!(result.best_move.to_uci(), "a1a8");
+        assert_eq!(mate_in(result.score), Some(1));
+
+        // black's only move is Kb8, then Rh8 mates two plies from the root
+        let result = search_to_depth("k7/8/1K6/8/8/8/8/7R b - - 0 1", 3);
+        assert_eq!(result.score, WHITE_WINS - 2);
+        assert_eq!(mate_in(result.score), Some(1));
+
+        // a mate for black is scored the same way, with the sign flipped
+        let result = search_to_depth("r5k1/8/8/8/8/8/5PPP/6K1 b - - 0 1", 2);
+        assert_eq!(result.score, BLACK_WINS + 1);
+        assert_eq!(mate_in(result.score), Some(-1));
+
+        assert_eq!(mate_in(250), None);
+        assert_eq!(mate_in(MAX_SCORE), None);
+    }
+
+    #[test]
+    fn test_mate_scores_in_the_tt_are_relative_to_the_node() {
+        for score in [0, 150, -420, MATE_THRESHOLD - 1, -MATE_THRESHOLD + 1] {
+            for ply in [0, 1, 5, 40] {
+                assert_eq!(score_from_tt(score_to_tt(score, ply), ply), score);
+            }
+        }
+        // a mate seen at `ply` is at least `ply` plies from the root
+        for distance in [1, 7, 60] {
+            for ply in [0, 1, distance] {
+                let white_mate = WHITE_WINS - distance as i16;
+                let black_mate = BLACK_WINS + distance as i16;
+                assert_eq!(score_from_tt(score_to_tt(white_mate, ply), ply), white_mate);
+                assert_eq!(score_from_tt(score_to_tt(black_mate, ply), ply), black_mate);
+            }
+        }
+        // a mate 7 plies from the root, found at ply 3, is 4 plies from that node on.
+        // Reached at ply 5 instead, it is 9 plies from the root.
+        let stored = score_to_tt(WHITE_WINS - 7, 3);
+        assert_eq!(stored, WHITE_WINS - 4);
+        assert_eq!(score_from_tt(stored, 5), WHITE_WINS - 9);
+        let stored = score_to_tt(BLACK_WINS + 7, 3);
+        assert_eq!(score_from_tt(stored, 5), BLACK_WINS + 9);
+        // evaluations are stored as they are
+        assert_eq!(score_to_tt(-420, 9), -420);
+    }
+}
@@ -0,0 +1,183 @@
+use crate::bit_boards::*;
+use crate::board::{Board, Piece};
+use crate::eval::{BISHOP_SCORE, KNIGHT_SCORE, PAWN_SCORE, QUEEN_SCORE, ROOK_SCORE};
+use crate::move_gen::promoted_piece;
+use crate::moves::Move;
+
+// A capture sequence can never be longer than the number of pieces on the board.
+const MAX_EXCHANGE_LENGTH: usize = 32;
+
+fn see_value(piece: Piece) -> i16 {
+    match piece {
+        Piece::None => 0,
+        Piece::Pawn { .. } => PAWN_SCORE,
+        Piece::Knight { .. } => KNIGHT_SCORE,
+        Piece::Bishop { .. } => BISHOP_SCORE,
+        Piece::Rook { .. } => ROOK_SCORE,
+        Piece::Queen { .. } => QUEEN_SCORE,
+        // The king is never captured in an exchange, it only recaptures if that is safe.
+        Piece::King { .. } => 0,
+    }
+}
+
+impl Board {
+    /// Static exchange evaluation: the material the side to move wins (or loses, if negative)
+    /// on the target square of `_move`, if both sides keep recapturing there with their least
+    /// valuable attacker and are free to stop whenever continuing would lose material.
+    pub fn see(&self, _move: &Move) -> i16 {
+        let to = _move.to();
+        let from = _move.from();
+
+        let mut gain = [0i16; MAX_EXCHANGE_LENGTH];
+        let mut occupied = self.all_pieces ^ square_index_to_bitboard(from);
+
+        let mut piece_on_square = self.pieces[from];
+        gain[0] = if _move.is_en_passant() {
+            let captured_index = if self.white_to_move { to - 8 } else { to + 8 };
+            occupied ^= square_index_to_bitboard(captured_index);
+            PAWN_SCORE
+        } else {
+            see_value(self.pieces[to])
+        };
+        if let Some(promotion) = _move.promotion_piece() {
+            piece_on_square = promoted_piece(promotion, self.white_to_move);
+            gain[0] += see_value(piece_on_square) - PAWN_SCORE;
+        }
+
+        let mut attackers = self.attackers_to(to, occupied) & occupied;
+        let mut white = !self.white_to_move;
+        let mut depth = 0;
+
+        loop {
+            let side_pieces = if white {
+                self.white_pieces
+            } else {
+                self.black_pieces
+            };
+            let Some((attacker_index, attacker)) =
+                self.least_valuable_attacker(attackers & side_pieces, white)
+            else {
+                break;
+            };
+
+            // The king may only recapture if the opponent has nothing left to take it with.
+            if matches!(attacker, Piece::King { .. }) && attackers & !side_pieces & occupied != 0 {
+                break;
+            }
+
+            depth += 1;
+            gain[depth] = see_value(piece_on_square) - gain[depth - 1];
+
+            occupied ^= square_index_to_bitboard(attacker_index);
+            // Removing the attacker may uncover a slider behind it.
+            attackers = self.attackers_to(to, occupied) & occupied;
+            piece_on_square = attacker;
+            white = !white;
+
+            if depth == MAX_EXCHANGE_LENGTH - 1 {
+                break;
+            }
+        }
+
+        // Walk the sequence back, each side stops capturing if that is better for it.
+        while depth > 0 {
+            gain[depth - 1] = -(-gain[depth - 1]).max(gain[depth]);
+            depth -= 1;
+        }
+        gain[0]
+    }
+
+    /// All pieces of both colors attacking `square`, with sliders blocked by `occupied`.
+    fn attackers_to(&self, square: usize, occupied: u64) -> u64 {
+        let rooks = self.white_rooks | self.black_rooks | self.white_queens | self.black_queens;
+        let bishops =
+            self.white_bishops | self.black_bishops | self.white_queens | self.black_queens;
+
+        (BLACK_FREE_PAWN_ATTACKS_LOOKUP[square] & self.white_pawns)
+            | (WHITE_FREE_PAWN_ATTACKS_LOOKUP[square] & self.black_pawns)
+            | (FREE_KNIGHT_LOOKUP[square] & (self.white_knights | self.black_knights))
+            | (FREE_KING_LOOKUP[square] & (self.white_king | self.black_king))
+            | (get_rook_moves(square as u32, occupied) & rooks)
+            | (get_bishop_moves(square, occupied) & bishops)
+    }
+
+    fn least_valuable_attacker(&self, attackers: u64, white: bool) -> Option<(usize, Piece)> {
+        let candidates = if white {
+            [
+                self.white_pawns,
+                self.white_knights,
+                self.white_bishops,
+                self.white_rooks,
+                self.white_queens,
+                self.white_king,
+            ]
+        } else {
+            [
+                self.black_pawns,
+                self.black_knights,
+                self.black_bishops,
+                self.black_rooks,
+                self.black_queens,
+                self.black_king,
+            ]
+        };
+        candidates
+            .into_iter()
+            .map(|pieces| pieces & attackers)
+            .find(|&pieces| pieces != 0)
+            .map(|pieces| {
+                let index = bitboard_to_square_index(pieces);
+                (index, self.pieces[index])
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn see_of(fen: &str, uci: &str) -> i16 {
+        let board = Board::from_fen(fen).unwrap();
+        let _move = board
+            .generate_legal_moves_temp()
+            .iter()
+            .copied()
+            .find(|m| m.to_uci() == uci)
+            .unwrap_or_else(|| panic!("{} is not legal in {}", uci, fen));
+        board.see(&_move)
+    }
+
+    #[test]
+    fn test_see() {
+        // undefended pawn
+        assert_eq!(
+            see_of("4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1", "e4d5"),
+            PAWN_SCORE
+        );
+        // rook takes a pawn defended by a pawn
+        assert_eq!(
+            see_of("4k3/8/2p5/3p4/8/8/3R4/4K3 w - - 0 1", "d2d5"),
+            PAWN_SCORE - ROOK_SCORE
+        );
+        // the second rook x-rays through the first one
+        assert_eq!(
+            see_of("3rk3/8/8/3p4/8/8/3R4/3RK3 w - - 0 1", "d2d5"),
+            PAWN_SCORE
+        );
+        // the king recaptures an undefended knight
+        assert_eq!(
+            see_of("4k3/8/8/8/8/2n5/8/3RK3 b - - 0 1", "c3d1"),
+            ROOK_SCORE - KNIGHT_SCORE
+        );
+        // but not a defended one
+        assert_eq!(
+            see_of("4k3/8/8/8/b7/2n5/8/3RK3 b - - 0 1", "c3d1"),
+            ROOK_SCORE
+        );
+        // en passant
+        assert_eq!(
+            see_of("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6"),
+            PAWN_SCORE
+        );
+    }
+}
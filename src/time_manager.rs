@@ -0,0 +1,188 @@
+use crate::bot::SearchSpecs;
+use std::time::{Duration, Instant};
+
+/// Time kept back on every move for the communication with the GUI.
+pub const DEFAULT_MOVE_OVERHEAD: Duration = Duration::from_millis(30);
+
+// Without a `movestogo` the time is divided as if this many moves are left.
+const DEFAULT_MOVES_TO_GO: u32 = 30;
+// The hard limit is at most this multiple of the soft limit,
+const HARD_LIMIT_FACTOR: u32 = 4;
+// and never more than this fraction of the time left.
+const MAX_TIME_FRACTION: f64 = 0.8;
+// Without a clock for the side to move, the search gets this `movetime`.
+const MISSING_CLOCK_MOVE_TIME: Duration = Duration::from_secs(5);
+
+/// Decides how long the bot searches.
+///
+/// No new iteration is started after the soft limit, which is extended while the best move
+/// keeps changing between depths. The hard limit stops the search in the middle of an iteration.
+#[derive(Debug, Clone, Copy)]
+pub struct TimeManager {
+    start: Instant,
+    soft_limit: Option<Duration>,
+    hard_limit: Option<Duration>,
+    // Decaying count of how often the best move changed in the last iterations.
+    best_move_instability: f64,
+}
+
+impl TimeManager {
+    /// Starts the clock for a search of the side to move.
+    pub fn new(specs: SearchSpecs, white_to_move: bool, move_overhead: Duration) -> Self {
+        let (soft_limit, hard_limit) = match specs {
+            SearchSpecs::Infinite => (None, None),
+            SearchSpecs::MoveTime(move_time) => Self::move_time_limits(move_time, move_overhead),
+            SearchSpecs::TimeLeft {
+                white_time,
+                black_time,
+                white_increment,
+                black_increment,
+                moves_to_go,
+            } => {
+                let (time, increment) = if white_to_move {
+                    (white_time, white_increment)
+                } else {
+                    (black_time, black_increment)
+                };
+                match time {
+                    None => Self::move_time_limits(MISSING_CLOCK_MOVE_TIME, move_overhead),
+                    Some(time) => {
+                        let (soft, hard) = Self::limits(
+                            time,
+                            increment.unwrap_or(Duration::ZERO),
+                            moves_to_go,
+                            move_overhead,
+                        );
+                        (Some(soft), Some(hard))
+                    }
+                }
+            }
+        };
+        Self {
+            start: Instant::now(),
+            soft_limit,
+            hard_limit,
+            best_move_instability: 0.0,
+        }
+    }
+
+    fn move_time_limits(
+        move_time: Duration,
+        move_overhead: Duration,
+    ) -> (Option<Duration>, Option<Duration>) {
+        let limit = move_time.saturating_sub(move_overhead);
+        (Some(limit), Some(limit))
+    }
+
+    fn limits(
+        time: Duration,
+        increment: Duration,
+        moves_to_go: Option<u8>,
+        move_overhead: Duration,
+    ) -> (Duration, Duration) {
+        let available = time.saturating_sub(move_overhead);
+        let moves_to_go = moves_to_go.map_or(DEFAULT_MOVES_TO_GO, u32::from).max(1);
+        let soft = available / moves_to_go + increment * 3 / 4;
+        let hard = (soft * HARD_LIMIT_FACTOR).min(available.mul_f64(MAX_TIME_FRACTION));
+        (soft.min(hard), hard)
+    }
+
+    /// Whether the search runs until it is stopped from outside.
+    pub fn is_infinite(&self) -> bool {
+        self.hard_limit.is_none()
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    /// The moment the search has to stop, even in the middle of an iteration.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.hard_limit.map(|limit| self.start + limit)
+    }
+
+    /// Called after every finished iteration, decides if the next one is started.
+    pub fn should_start_iteration(&mut self, best_move_changed: bool) -> bool {
+        self.best_move_instability =
+            self.best_move_instability / 2.0 + if best_move_changed { 1.0 } else { 0.0 };
+        let (Some(soft_limit), Some(hard_limit)) = (self.soft_limit, self.hard_limit) else {
+            return true;
+        };
+        let extended_limit = soft_limit
+            .mul_f64(1.0 + self.best_move_instability / 2.0)
+            .min(hard_limit);
+        self.elapsed() < extended_limit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time_left(time: u64, increment: u64, moves_to_go: Option<u8>) -> SearchSpecs {
+        SearchSpecs::TimeLeft {
+            white_time: Some(Duration::from_millis(time)),
+            black_time: Some(Duration::from_millis(time / 2)),
+            white_increment: Some(Duration::from_millis(increment)),
+            black_increment: None,
+            moves_to_go,
+        }
+    }
+
+    #[test]
+    fn test_limits() {
+        let overhead = Duration::from_millis(30);
+        let manager = TimeManager::new(time_left(60_030, 0, None), true, overhead);
+        assert_eq!(manager.soft_limit, Some(Duration::from_millis(2000)));
+        assert_eq!(manager.hard_limit, Some(Duration::from_millis(8000)));
+
+        // the time is split over the moves to go, and most of the increment is used
+        let manager = TimeManager::new(time_left(10_030, 1000, Some(10)), true, overhead);
+        assert_eq!(manager.soft_limit, Some(Duration::from_millis(1750)));
+
+        // black has half the time and no increment
+        let manager = TimeManager::new(time_left(60_060, 1000, None), false, overhead);
+        assert_eq!(manager.soft_limit, Some(Duration::from_millis(1000)));
+
+        // never more than a fraction of what is left, even with a large increment
+        let manager = TimeManager::new(time_left(1_030, 5000, None), true, overhead);
+        assert_eq!(manager.hard_limit, Some(Duration::from_millis(800)));
+        assert_eq!(manager.soft_limit, manager.hard_limit);
+
+        let manager = TimeManager::new(
+            SearchSpecs::MoveTime(Duration::from_millis(1000)),
+            true,
+            overhead,
+        );
+        assert_eq!(manager.hard_limit, Some(Duration::from_millis(970)));
+        assert!(TimeManager::new(SearchSpecs::Infinite, true, overhead).is_infinite());
+
+        // only the clock of the opponent is known
+        let black_clock_only = SearchSpecs::TimeLeft {
+            white_time: None,
+            black_time: Some(Duration::from_millis(60_000)),
+            white_increment: None,
+            black_increment: None,
+            moves_to_go: None,
+        };
+        let manager = TimeManager::new(black_clock_only, true, overhead);
+        assert!(!manager.is_infinite());
+        assert_eq!(
+            manager.hard_limit,
+            Some(MISSING_CLOCK_MOVE_TIME.saturating_sub(overhead))
+        );
+    }
+
+    #[test]
+    fn test_unstable_best_move_extends_time() {
+        let mut manager = TimeManager::new(
+            SearchSpecs::MoveTime(Duration::from_secs(100)),
+            true,
+            Duration::ZERO,
+        );
+        manager.soft_limit = Some(Duration::from_millis(200));
+        manager.start = Instant::now() - Duration::from_millis(250);
+        assert!(!manager.should_start_iteration(false));
+        assert!(manager.should_start_iteration(true));
+    }
+}
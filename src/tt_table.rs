@@ -1,7 +1,10 @@
 use crate::moves::Move;
 use std::mem;
+use std::sync::atomic::{AtomicU64, Ordering};
 
-pub const TT_SIZE: usize = 1 << 20;
+/// The table size in megabytes if none is configured.
+pub const DEFAULT_SIZE_MB: usize = 16;
+const BUCKET_SIZE: usize = 4;
 #[derive(Clone, Copy, PartialEq, Debug)]
 #[repr(u64)]
 pub enum NodeType {
@@ -45,7 +48,8 @@ impl TT_Entry {
     const NUM_RESETS_SHIFT: u64 = Self::NODE_TYPE_SHIFT + 2;
     const NUM_RESETS_MASK: u64 = ((1 << 7) - 1) << Self::NUM_RESETS_SHIFT;
 
-    // [Bits 56-63] 8 bits are unused and available for future data.
+    // [Bits 56-63] 8 bits for the age, the search the entry was written in
+    const AGE_SHIFT: u64 = Self::NUM_RESETS_SHIFT + 7;
 
     /// Creates a new transposition table entry.
     #[inline(always)]
@@ -71,6 +75,15 @@ impl TT_Entry {
         Self { zobrist_hash, data }
     }
 
+    #[inline(always)]
+    fn with_age(self, age: u8) -> Self {
+        let data = (self.data & !(0xFF << Self::AGE_SHIFT)) | ((age as u64) << Self::AGE_SHIFT);
+        Self {
+            zobrist_hash: self.zobrist_hash,
+            data,
+        }
+    }
+
     #[inline(always)]
     pub fn init() -> Self {
         TT_Entry {
@@ -118,39 +131,131 @@ impl TT_Entry {
     pub fn num_resetting_moves(&self) -> u8 {
         ((self.data & Self::NUM_RESETS_MASK) >> Self::NUM_RESETS_SHIFT) as u8
     }
+
+    #[inline(always)]
+    pub fn age(&self) -> u8 {
+        (self.data >> Self::AGE_SHIFT) as u8
+    }
+
+    #[inline(always)]
+    fn quality(&self) -> i32 {
+        (self.depth() as i32 * 3) + self.node_type() as i32
+    }
+}
+/// One slot of the table, shared between all search threads.
+///
+/// Both halves are written separately, so another thread may see the key of one entry
+/// together with the data of another. The key is therefore stored xored with the data:
+/// a torn entry no longer matches any position and is treated as a miss.
+#[derive(Default)]
+struct AtomicEntry {
+    key: AtomicU64,
+    data: AtomicU64,
 }
+
+impl AtomicEntry {
+    #[inline(always)]
+    fn load(&self) -> TT_Entry {
+        let key = self.key.load(Ordering::Relaxed);
+        let data = self.data.load(Ordering::Relaxed);
+        TT_Entry {
+            zobrist_hash: key ^ data,
+            data,
+        }
+    }
+
+    #[inline(always)]
+    fn store(&self, entry: TT_Entry) {
+        self.key
+            .store(entry.zobrist_hash ^ entry.data, Ordering::Relaxed);
+        self.data.store(entry.data, Ordering::Relaxed);
+    }
+}
+
+/// The entries a position can be stored in, one cache line.
+#[derive(Default)]
+#[repr(align(64))]
+struct Bucket {
+    entries: [AtomicEntry; BUCKET_SIZE],
+}
+
+/// The transposition table. It is lock-free, so one table can be shared by reference
+/// between all threads of a search.
 #[allow(non_camel_case_types)]
 pub struct TT_Table {
     //vec because rust can allocate an array directly on the heap and this would cause a stack overflow
-    tt_table: Vec<TT_Entry>,
+    tt_table: Vec<Bucket>,
+    // The number of buckets is a power of two, so the index is the hash masked with this.
+    index_mask: usize,
+    // Increased with every search, so entries from old searches are replaced first.
+    age: u8,
+}
+
+impl Default for TT_Table {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl TT_Table {
     pub fn new() -> Self {
+        Self::with_size_mb(DEFAULT_SIZE_MB)
+    }
+
+    /// Creates a table of at most `size_mb` megabytes, but with at least one bucket.
+    pub fn with_size_mb(size_mb: usize) -> Self {
+        let max_buckets = (size_mb * 1024 * 1024 / mem::size_of::<Bucket>()).max(1);
+        // Round down to a power of two
+        let num_buckets = 1 << max_buckets.ilog2();
         TT_Table {
-            tt_table: vec![TT_Entry::init(); TT_SIZE],
+            tt_table: (0..num_buckets).map(|_| Bucket::default()).collect(),
+            index_mask: num_buckets - 1,
+            age: 0,
         }
     }
-    //TODO: find more performant output
-    #[inline(always)]
-    pub fn probe(&self, outside_zobrist: u64) -> Option<&TT_Entry> {
-        // This rare edge case will be almost never hit, and when depth = 0 will make it discard the value
-        // if outside_zobrist & (TT_INDEX_MASK | !TT_Entry::TT_INFO_MASK) == 0 {
-        //     return None
-        // }
-        let i = (outside_zobrist as usize) % TT_SIZE;
-        let maybe_hit = &self.tt_table[i];
-        //decide if it is a hash hit, because we know by indexing, the last part (the part masked to i) is also the same
-        if maybe_hit.zobrist_hash == outside_zobrist {
-            Some(maybe_hit)
-        } else {
-            None
+
+    /// Empties the table, e.g. for a new game.
+    pub fn clear(&mut self) {
+        for bucket in &mut self.tt_table {
+            *bucket = Bucket::default();
         }
+        self.age = 0;
+    }
+
+    /// Marks the start of a new search, entries stored before it become the first to be replaced.
+    pub fn new_search(&mut self) {
+        self.age = self.age.wrapping_add(1);
+    }
+
+    #[inline(always)]
+    fn bucket(&self, zobrist: u64) -> &Bucket {
+        &self.tt_table[zobrist as usize & self.index_mask]
+    }
+
+    #[inline(always)]
+    pub fn probe(&self, outside_zobrist: u64) -> Option<TT_Entry> {
+        self.bucket(outside_zobrist)
+            .entries
+            .iter()
+            .map(AtomicEntry::load)
+            .find(|entry| entry.zobrist_hash == outside_zobrist)
+    }
+
+    /// How full the table is, in permille, estimated from the first thousand entries.
+    /// Only entries of the current search are counted.
+    pub fn hashfull(&self) -> u16 {
+        let sample = &self.tt_table[..self.tt_table.len().min(1000 / BUCKET_SIZE)];
+        let used = sample
+            .iter()
+            .flat_map(|bucket| bucket.entries.iter().map(AtomicEntry::load))
+            .filter(|entry| entry.zobrist_hash != 0 && entry.age() == self.age)
+            .count();
+        (used * 1000 / (sample.len() * BUCKET_SIZE)) as u16
     }
 
     #[inline(always)]
     pub fn insert(
-        &mut self,
+        &self,
         zobrist: u64,
         eval: i16,
         depth: u8,
@@ -160,35 +265,54 @@ impl TT_Table {
         //TODO: overflow
         num_resetting_moves: u8,
     ) {
-        //TODO: insertion strategy
-        let index = zobrist as usize % TT_SIZE;
-        let existing_entry = &self.tt_table[index];
-
-        let is_same_position = existing_entry.zobrist_hash == zobrist;
-
-        let new_node_quality = (depth as u16 * 3) + node_type as u16;
-        let existing_node_quality =
-            (existing_entry.depth() as u16 * 3) + existing_entry.node_type() as u16;
-
-        if !is_same_position || new_node_quality >= existing_node_quality {
-            self.tt_table[index] = TT_Entry::new(
-                zobrist,
-                depth,
-                eval,
-                node_type,
-                best_move,
-                halfmove_clock,
-                num_resetting_moves,
-            );
+        let new_entry = TT_Entry::new(
+            zobrist,
+            depth,
+            eval,
+            node_type,
+            best_move,
+            halfmove_clock,
+            num_resetting_moves,
+        )
+        .with_age(self.age);
+        let entries = &self.bucket(zobrist).entries;
+
+        // The same position is only overwritten by a result at least as good, unless it is old.
+        if let Some(existing_slot) = entries
+            .iter()
+            .find(|slot| slot.load().zobrist_hash == zobrist)
+        {
+            let existing_entry = existing_slot.load();
+            if existing_entry.age() != self.age || new_entry.quality() >= existing_entry.quality() {
+                existing_slot.store(new_entry);
+            }
+            return;
         }
+
+        // Otherwise an empty slot is used, or the one that is the least useful: shallow and old.
+        let replaced_slot = entries
+            .iter()
+            .min_by_key(|slot| {
+                let entry = slot.load();
+                if entry.zobrist_hash == 0 {
+                    i32::MIN
+                } else {
+                    entry.quality() - 8 * self.age.wrapping_sub(entry.age()) as i32
+                }
+            })
+            .unwrap();
+        replaced_slot.store(new_entry);
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    #[cfg(feature = "rand")]
     use rand::distr::Distribution;
+    #[cfg(feature = "rand")]
     use rand::distr::Uniform;
+    #[cfg(feature = "rand")]
     use rand::{Rng, rng};
 
     // Helper function to create a new, heap-allocated TT_Table to prevent a stack overflow,
@@ -197,6 +321,7 @@ mod tests {
         TT_Table::new()
     }
 
+    #[cfg(feature = "rand")]
     #[test]
     fn test_tt_entry_encoding_decoding_randomized() {
         let mut rng = rng();
@@ -210,22 +335,19 @@ mod tests {
                 _ => NodeType::AllNode,
             };
 
-            let entry = TT_Entry::new(zobrist_hash, depth, eval, flag);
+            let entry = TT_Entry::new(zobrist_hash, depth, eval, flag, Move::null_move(), 0, 0);
 
             assert_eq!(entry.eval(), eval, "eval mismatch");
             assert_eq!(entry.depth(), depth, "depth mismatch");
             assert_eq!(entry.node_type(), flag, "node_type mismatch");
-            assert_eq!(
-                entry.zobrist_hash,
-                zobrist_hash & !TT_Entry::TT_INFO_MASK,
-                "zobrist_hash_part mismatch"
-            );
+            assert_eq!(entry.zobrist_hash, zobrist_hash, "zobrist_hash mismatch");
         }
     }
 
+    #[cfg(feature = "rand")]
     #[test]
     fn test_tt_table_insert_and_index_hit() {
-        let mut tt_table = new_test_tt_table();
+        let tt_table = new_test_tt_table();
         let mut rng = rng();
         let depth_range: Uniform<u8> = Uniform::new(1, 120).unwrap();
         let eval_range: Uniform<i16> = Uniform::new(-30000, 30000).unwrap();
@@ -241,7 +363,7 @@ mod tests {
                 _ => NodeType::AllNode,
             };
 
-            tt_table.insert(zobrist_hash, eval, depth, flag);
+            tt_table.insert(zobrist_hash, eval, depth, flag, Move::null_move(), 0, 0);
             let result = tt_table.probe(zobrist_hash);
 
             assert!(
@@ -257,6 +379,7 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "rand")]
     #[test]
     fn test_tt_table_index_miss_for_non_existent_entry() {
         let tt_table = new_test_tt_table();
@@ -272,4 +395,75 @@ mod tests {
             );
         }
     }
+
+    fn insert_at_depth(tt_table: &TT_Table, zobrist_hash: u64, depth: u8) {
+        tt_table.insert(
+            zobrist_hash,
+            0,
+            depth,
+            NodeType::PvNode,
+            Move::null_move(),
+            0,
+            0,
+        );
+    }
+
+    #[test]
+    fn test_with_size_mb_rounds_down_to_a_power_of_two() {
+        for size_mb in [1, 3, 16, 100] {
+            let buckets = TT_Table::with_size_mb(size_mb).tt_table.len();
+            let bucket_bytes = mem::size_of::<Bucket>();
+            assert!(buckets.is_power_of_two(), "{} MB", size_mb);
+            assert!(
+                buckets * bucket_bytes <= size_mb * 1024 * 1024,
+                "{} MB",
+                size_mb
+            );
+            assert!(
+                buckets * 2 * bucket_bytes > size_mb * 1024 * 1024,
+                "{} MB",
+                size_mb
+            );
+        }
+    }
+
+    #[test]
+    fn test_old_entries_are_replaced_first() {
+        let mut tt_table = TT_Table::with_size_mb(1);
+        // all of these land in the same bucket
+        let hash = |i: u64| 7 | (i << 32);
+
+        insert_at_depth(&tt_table, hash(1), 6);
+        tt_table.new_search();
+        for i in 2..=BUCKET_SIZE as u64 {
+            insert_at_depth(&tt_table, hash(i), 5);
+        }
+        // the bucket is full, the old entry goes even though it is the deepest
+        insert_at_depth(&tt_table, hash(10), 1);
+        assert!(tt_table.probe(hash(1)).is_none());
+        assert_eq!(tt_table.probe(hash(10)).unwrap().depth(), 1);
+        for i in 2..=BUCKET_SIZE as u64 {
+            assert_eq!(tt_table.probe(hash(i)).unwrap().depth(), 5);
+        }
+    }
+
+    #[test]
+    fn test_clear_and_hashfull() {
+        let mut tt_table = TT_Table::with_size_mb(1);
+        assert_eq!(tt_table.hashfull(), 0);
+
+        for i in 1..100 {
+            insert_at_depth(&tt_table, i, 3);
+        }
+        let hashfull = tt_table.hashfull();
+        assert!(hashfull > 0);
+        for i in 100..200 {
+            insert_at_depth(&tt_table, i, 3);
+        }
+        assert!(tt_table.hashfull() > hashfull);
+
+        tt_table.clear();
+        assert_eq!(tt_table.hashfull(), 0);
+        assert!((1..200).all(|i| tt_table.probe(i).is_none()));
+    }
 }